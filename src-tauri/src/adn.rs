@@ -0,0 +1,131 @@
+// Pacotes baixados do Ambiente de Dados Nacional (ADN) do Sistema Nacional NFS-e:
+// JSON com LoteDFe[].ArquivoXml (ou nfseXmlGZipB64), cada um com o XML em gzip + base64.
+// Os eventos do lote (TipoDocumento EVENTO) não são notas: cancelamento e cancelamento por
+// substituição são aplicados depois à nota com a mesma chave de acesso.
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use std::io::Read;
+
+use crate::cancellation::NotaStatus;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Evento registrado sobre uma NFS-e (infEvento/pedRegEvento/infPedReg)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Event {
+    // Chave de acesso da nota (chNFSe)
+    pub(crate) chave: String,
+    // Tipo do evento com a descrição (ex.: "e101101 Cancelamento de NFS-e")
+    pub(crate) description: String,
+    // Situação que o evento leva para a nota; ausente nos eventos que não a mudam
+    pub(crate) status: Option<NotaStatus>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Package {
+    pub(crate) documents: Vec<String>,
+    pub(crate) events: Vec<Event>,
+}
+
+pub(crate) fn is_package(content: &str) -> bool {
+    content.trim_start().starts_with('{')
+}
+
+// Expandir todos os documentos e eventos do pacote, na ordem do lote
+pub(crate) fn decode_package(content: &str) -> Result<Package, String> {
+    let json: Value = serde_json::from_str(content).map_err(|e| format!("JSON do ADN inválido: {}", e))?;
+
+    // Consulta de documento único
+    if let Some(arquivo) = json.get("nfseXmlGZipB64").and_then(|v| v.as_str()) {
+        return Ok(Package { documents: vec![decode_arquivo(arquivo)?], events: Vec::new() });
+    }
+
+    let lote = json
+        .get("LoteDFe")
+        .and_then(|v| v.as_array())
+        .ok_or("Pacote do ADN sem LoteDFe")?;
+
+    let mut pacote = Package::default();
+    for (indice, dfe) in lote.iter().enumerate() {
+        let arquivo = dfe
+            .get("ArquivoXml")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| format!("LoteDFe[{}] sem ArquivoXml", indice))?;
+        let xml = decode_arquivo(arquivo).map_err(|e| format!("LoteDFe[{}]: {}", indice, e))?;
+        if dfe.get("TipoDocumento").and_then(|v| v.as_str()).is_some_and(|tipo| tipo == "EVENTO") {
+            pacote.events.push(read_event(&xml).map_err(|e| format!("LoteDFe[{}]: {}", indice, e))?);
+        } else {
+            pacote.documents.push(xml);
+        }
+    }
+
+    // Pacote só de eventos é válido: os eventos se aplicam às notas de outros arquivos do lote
+    if pacote.documents.is_empty() && pacote.events.is_empty() {
+        return Err("Nenhum documento NFS-e ou evento encontrado no pacote do ADN".to_string());
+    }
+    Ok(pacote)
+}
+
+// Número da nota dentro da chave de acesso: 13 posições depois de cMun, ambiente e inscrição
+fn numero_da_chave(chave: &str) -> String {
+    let numero = chave.get(23..36).unwrap_or(chave).trim_start_matches('0');
+    if numero.is_empty() { "0".to_string() } else { numero.to_string() }
+}
+
+// e101101 = cancelamento, e105102 = cancelamento por substituição; os demais (manifestação,
+// análise fiscal...) não mudam a situação da nota
+fn read_event(xml: &str) -> Result<Event, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| format!("evento inválido: {}", e))?;
+    let texto = |node: roxmltree::Node, nome: &str| {
+        node.descendants()
+            .find(|n| n.has_tag_name(nome))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+    let raiz = doc.root_element();
+    let detalhe = raiz.descendants().find(|n| {
+        let nome = n.tag_name().name();
+        nome.len() == 7 && nome.starts_with('e') && nome[1..].bytes().all(|b| b.is_ascii_digit())
+    });
+
+    let tipo = detalhe.map(|n| n.tag_name().name()).unwrap_or("evento");
+    let descricao = detalhe.and_then(|n| texto(n, "xDesc"));
+    let data = texto(raiz, "dhProc").or_else(|| texto(raiz, "dhEvento"));
+    let motivo = detalhe.and_then(|n| texto(n, "cMotivo"));
+
+    let status = match tipo {
+        "e101101" => Some(NotaStatus { cancelled: true, cancelled_at: data, cancellation_code: motivo, substituted_by: None }),
+        "e105102" => {
+            // Sem a chave da substituta, a nota fica pelo menos como cancelada
+            let substituta = detalhe.and_then(|n| texto(n, "chSubstituta")).map(|chave| numero_da_chave(&chave));
+            Some(NotaStatus { cancelled: substituta.is_none(), cancelled_at: data, cancellation_code: motivo, substituted_by: substituta })
+        }
+        _ => None,
+    };
+
+    Ok(Event {
+        chave: texto(raiz, "chNFSe").unwrap_or_default(),
+        description: descricao.map(|d| format!("{} {}", tipo, d)).unwrap_or_else(|| tipo.to_string()),
+        status,
+    })
+}
+
+fn decode_arquivo(arquivo: &str) -> Result<String, String> {
+    let bytes = STANDARD
+        .decode(arquivo.trim())
+        .map_err(|e| format!("base64 inválido: {}", e))?;
+
+    // Alguns ambientes devolvem o XML só em base64, sem compactação
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return String::from_utf8(bytes).map_err(|e| e.to_string());
+    }
+
+    let mut xml = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("gzip inválido: {}", e))?;
+    Ok(xml)
+}
@@ -0,0 +1,164 @@
+// Notas canceladas e substituídas. O ABRASF informa o cancelamento e a substituição no
+// CompNfse, fora da InfNfse (NfseCancelamento, NfseSubstituicao); a Paulistana usa StatusNFe.
+// A nota substituta aponta para a original em InfNfse/NfseSubstituida.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::nota::text_at;
+use crate::report::NotaReport;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CancelledPolicy {
+    // Fora da saída, só no relatório
+    #[default]
+    Exclude,
+    // Na saída, com os dados de cancelamento/substituição
+    Flag,
+    // Num documento à parte (<nome>-canceladas)
+    Separate,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct NotaStatus {
+    pub(crate) cancelled: bool,
+    pub(crate) cancelled_at: Option<String>,
+    pub(crate) cancellation_code: Option<String>,
+    // Número da nota que substituiu esta
+    pub(crate) substituted_by: Option<String>,
+}
+
+impl NotaStatus {
+    // Substituída também conta como cancelada: a receita está na substituta
+    pub(crate) fn is_active(&self) -> bool {
+        !self.cancelled && self.substituted_by.is_none()
+    }
+}
+
+fn first_text(value: &Value, paths: &[&str]) -> Option<String> {
+    paths.iter().find_map(|path| text_at(value, path))
+}
+
+// Situação da nota a partir do elemento de origem (CompNfse, NFe...)
+pub(crate) fn read(source: &Value) -> NotaStatus {
+    let mut status = NotaStatus::default();
+
+    if source.get("NfseCancelamento").is_some() {
+        status.cancelled = true;
+        status.cancelled_at = first_text(
+            source,
+            &[
+                "NfseCancelamento/Confirmacao/DataHora",
+                "NfseCancelamento/Confirmacao/InfConfirmacaoCancelamento/DataHora",
+                "NfseCancelamento/Confirmacao/DataHoraCancelamento",
+            ],
+        );
+        status.cancellation_code = first_text(
+            source,
+            &[
+                "NfseCancelamento/Confirmacao/Pedido/InfPedidoCancelamento/CodigoCancelamento",
+                "NfseCancelamento/Confirmacao/InfPedidoCancelamento/CodigoCancelamento",
+            ],
+        );
+    }
+    // Paulistana: N = normal, C = cancelada
+    if text_at(source, "StatusNFe").as_deref() == Some("C") {
+        status.cancelled = true;
+    }
+
+    status.substituted_by = first_text(
+        source,
+        &["NfseSubstituicao/SubstituicaoNfse/NfseSubstituidora", "NfseSubstituicao/NfseSubstituidora"],
+    );
+    status
+}
+
+// Levar a situação para a nota (mesmos elementos do CompNfse) e para o relatório
+pub(crate) fn apply(status: &NotaStatus, nota: &mut Value, report: &mut NotaReport) {
+    let Some(campos) = nota.as_object_mut() else {
+        return;
+    };
+    if status.cancelled {
+        let mut cancelamento = Map::new();
+        if let Some(data) = &status.cancelled_at {
+            cancelamento.insert("DataHora".to_string(), json!(data));
+        }
+        if let Some(codigo) = &status.cancellation_code {
+            cancelamento.insert("CodigoCancelamento".to_string(), json!(codigo));
+        }
+        campos.insert("NfseCancelamento".to_string(), Value::Object(cancelamento));
+    }
+    if let Some(substituidora) = &status.substituted_by {
+        campos.insert("NfseSubstituicao".to_string(), json!({ "NfseSubstituidora": substituidora }));
+    }
+    report.status = status.clone();
+}
+
+// Evento do ADN levado à nota com a mesma chave de acesso (CodigoVerificacao no Padrão
+// Nacional), somado ao que a nota já informava. Devolve false se a nota não está no lote.
+pub(crate) fn apply_event(chave: &str, status: &NotaStatus, notas: &mut [Value], reports: &mut [NotaReport]) -> bool {
+    let Some(posicao) = notas.iter().position(|nota| !chave.is_empty() && text_at(nota, "CodigoVerificacao").as_deref() == Some(chave)) else {
+        return false;
+    };
+    let atual = &reports[posicao].status;
+    let status = NotaStatus {
+        cancelled: atual.cancelled || status.cancelled,
+        cancelled_at: status.cancelled_at.clone().or_else(|| atual.cancelled_at.clone()),
+        cancellation_code: status.cancellation_code.clone().or_else(|| atual.cancellation_code.clone()),
+        substituted_by: status.substituted_by.clone().or_else(|| atual.substituted_by.clone()),
+    };
+    apply(&status, &mut notas[posicao], &mut reports[posicao]);
+    true
+}
+
+fn same_numero(a: &str, b: &str) -> bool {
+    a.trim_start_matches('0') == b.trim_start_matches('0')
+}
+
+// A numeração é de cada prestador: a substituição só liga notas do mesmo CNPJ/CPF
+fn prestador(nota: &Value) -> String {
+    text_at(nota, "PrestadorServico/IdentificacaoPrestador/Cnpj")
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect()
+}
+
+// Ligar substituídas e substitutas do mesmo lote. A substituta (NfseSubstituida) marca a
+// original do mesmo prestador como substituída mesmo quando o CompNfse da original não traz a
+// substituição.
+pub(crate) fn link(notas: &mut [Value], reports: &mut [NotaReport]) {
+    // (prestador, número substituído, número da substituta)
+    let substituicoes: Vec<(String, String, String)> = notas
+        .iter()
+        .filter_map(|nota| Some((prestador(nota), text_at(nota, "NfseSubstituida")?, text_at(nota, "Numero")?)))
+        .collect();
+
+    for (nota, report) in notas.iter_mut().zip(reports.iter_mut()) {
+        let cnpj = prestador(nota);
+        let numero = text_at(nota, "Numero").unwrap_or_default();
+        let substituicao = substituicoes.iter().find(|(outro, original, _)| *outro == cnpj && same_numero(original, &numero));
+        if let Some((_, _, substituta)) = substituicao {
+            if report.status.substituted_by.is_none() {
+                let status = NotaStatus { substituted_by: Some(substituta.clone()), ..report.status.clone() };
+                apply(&status, nota, report);
+            }
+        }
+    }
+
+    let numeros: Vec<(String, String)> = notas.iter().filter_map(|nota| Some((prestador(nota), text_at(nota, "Numero")?))).collect();
+    let no_lote = |cnpj: &str, numero: &str| numeros.iter().any(|(outro, outro_numero)| outro == cnpj && same_numero(outro_numero, numero));
+    for (nota, report) in notas.iter().zip(reports.iter_mut()) {
+        let cnpj = prestador(nota);
+        if let Some(substituta) = report.status.substituted_by.clone() {
+            if !no_lote(&cnpj, &substituta) {
+                report.warn(format!("Substituída pela nota {}, que não está neste lote", substituta));
+            }
+        }
+        if let Some(original) = text_at(nota, "NfseSubstituida") {
+            if !no_lote(&cnpj, &original) {
+                report.warn(format!("Substitui a nota {}, que não está neste lote", original));
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Perfis de empresa: valores usados quando a nota não traz um campo (município, item da
+// lista de serviços, datas, documento do tomador). Ficam gravados em company_profiles.json
+// no diretório de configuração do app; um deles fica selecionado para as conversões.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::dates::DateSettings;
+use crate::municipalities::MunicipalitySettings;
+use crate::nota::{get_path, set_path, str_or};
+use crate::report::NotaReport;
+use crate::services::ServiceSettings;
+use crate::taxes::TaxSettings;
+
+const PROFILES_FILE: &str = "company_profiles.json";
+const DEFAULT_PROFILE_NAME: &str = "Padrão";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub(crate) enum Fallback {
+    Value { value: Value },
+    // Sem valor padrão: a conversão falha se o campo estiver ausente
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FieldDefault {
+    // Caminho do campo na nota interna (ex.: Servico/CodigoMunicipio)
+    pub(crate) field: String,
+    #[serde(flatten)]
+    pub(crate) fallback: Fallback,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CompanyProfile {
+    pub(crate) name: String,
+    pub(crate) defaults: Vec<FieldDefault>,
+    // Formato e fuso de DataEmissao e Competencia na saída
+    #[serde(default)]
+    pub(crate) dates: DateSettings,
+    // Tolerância e representação da alíquota na conferência dos valores
+    #[serde(default)]
+    pub(crate) taxes: TaxSettings,
+    // Formato do ItemListaServico e associação de CNAE a item da lista
+    #[serde(default)]
+    pub(crate) services: ServiceSettings,
+    #[serde(default)]
+    pub(crate) municipalities: MunicipalitySettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CompanyProfiles {
+    pub(crate) selected: String,
+    pub(crate) profiles: Vec<CompanyProfile>,
+}
+
+fn value_default(field: &str, value: Value) -> FieldDefault {
+    FieldDefault { field: field.to_string(), fallback: Fallback::Value { value } }
+}
+
+// Valores que o conversor sempre usou (herdados do conversor.js)
+impl Default for CompanyProfile {
+    fn default() -> Self {
+        CompanyProfile {
+            name: DEFAULT_PROFILE_NAME.to_string(),
+            defaults: vec![
+                value_default("DataEmissao", json!("2025-01-01T00:00:00")),
+                value_default("Competencia", json!("2025-01-01T00:00:00")),
+                value_default("Servico/ItemListaServico", json!("1005")),
+                value_default("Servico/CodigoMunicipio", json!("2602902")),
+                value_default("TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj", json!("00000000000000")),
+            ],
+            dates: DateSettings::default(),
+            taxes: TaxSettings::default(),
+            services: ServiceSettings::default(),
+            municipalities: MunicipalitySettings::default(),
+        }
+    }
+}
+
+impl Default for CompanyProfiles {
+    fn default() -> Self {
+        CompanyProfiles {
+            selected: DEFAULT_PROFILE_NAME.to_string(),
+            profiles: vec![CompanyProfile::default()],
+        }
+    }
+}
+
+impl CompanyProfile {
+    // Preencher os campos ausentes da nota com os valores do perfil
+    pub(crate) fn apply(&self, nota: &mut Value, report: &mut NotaReport) -> Result<(), String> {
+        for default in &self.defaults {
+            if !matches!(get_path(nota, &default.field), None | Some(Value::Null)) {
+                continue;
+            }
+            match &default.fallback {
+                Fallback::Value { value } => {
+                    if let Some(campos) = nota.as_object_mut() {
+                        set_path(campos, &default.field, value.clone());
+                        report.defaulted(&default.field, value);
+                    }
+                }
+                Fallback::Fail => {
+                    return Err(format!(
+                        "Nota {}: campo {} ausente e o perfil \"{}\" não define valor padrão",
+                        str_or(nota, "Numero", "?"),
+                        default.field,
+                        self.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CompanyProfiles {
+    pub(crate) fn selected_profile(&self) -> CompanyProfile {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == self.selected)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn select(&mut self, name: &str) -> Result<(), String> {
+        if !self.profiles.iter().any(|profile| profile.name == name) {
+            return Err(format!("Perfil de empresa \"{}\" não encontrado", name));
+        }
+        self.selected = name.to_string();
+        Ok(())
+    }
+
+    // Criar ou substituir o perfil com o mesmo nome
+    pub(crate) fn upsert(&mut self, profile: CompanyProfile) -> Result<(), String> {
+        if profile.name.trim().is_empty() {
+            return Err("O perfil de empresa precisa de um nome".to_string());
+        }
+        profile.dates.validate()?;
+        profile.taxes.validate()?;
+        profile.services.validate()?;
+        match self.profiles.iter_mut().find(|existente| existente.name == profile.name) {
+            Some(existente) => *existente = profile,
+            None => self.profiles.push(profile),
+        }
+        Ok(())
+    }
+}
+
+fn profiles_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(PROFILES_FILE))
+}
+
+// Sem arquivo gravado ainda, vale o perfil padrão
+pub(crate) fn load(app: &AppHandle) -> Result<CompanyProfiles, String> {
+    let path = profiles_path(app)?;
+    if !path.exists() {
+        return Ok(CompanyProfiles::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("Perfis de empresa inválidos ({}): {}", path.display(), e))
+}
+
+pub(crate) fn save(app: &AppHandle, profiles: &CompanyProfiles) -> Result<(), String> {
+    let path = profiles_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())
+}
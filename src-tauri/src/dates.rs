@@ -0,0 +1,204 @@
+// Datas da nota (DataEmissao e Competencia): reconhece as variações que os provedores mandam
+// e normaliza para o formato e a política de fuso do perfil de empresa.
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write;
+
+use crate::nota::{set_path, text_at};
+use crate::report::NotaReport;
+
+// Campo e se o horário passa para o fuso do perfil. A competência é uma data de calendário:
+// muda só o formato, nunca o dia ou o mês.
+const CAMPOS_DATA: &[(&str, bool)] = &[("DataEmissao", true), ("Competencia", false)];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct DateSettings {
+    // Formato de saída no padrão strftime (ex.: "%Y-%m-%dT%H:%M:%S%:z")
+    pub(crate) format: String,
+    // Fuso para onde converter as datas; sem ele vale o horário como veio na origem
+    pub(crate) timezone: Option<String>,
+    // Fuso das datas que não informam nenhum (horário de Brasília)
+    pub(crate) assumed_offset: String,
+}
+
+impl Default for DateSettings {
+    fn default() -> Self {
+        DateSettings {
+            format: "%Y-%m-%dT%H:%M:%S".to_string(),
+            timezone: None,
+            assumed_offset: "-03:00".to_string(),
+        }
+    }
+}
+
+// Data reconhecida: o horário como escrito na origem e o fuso, se informado
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ParsedDate {
+    pub(crate) local: NaiveDateTime,
+    pub(crate) offset: Option<FixedOffset>,
+}
+
+fn parse_offset(text: &str) -> Option<FixedOffset> {
+    if text == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    DateTime::parse_from_str(&format!("2000-01-01T00:00:00{}", text), "%Y-%m-%dT%H:%M:%S%:z")
+        .ok()
+        .map(|dt| *dt.offset())
+}
+
+fn first_day(ano: &str, mes: &str) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(ano.parse().ok()?, mes.parse().ok()?, 1).map(|d| d.and_time(NaiveTime::MIN))
+}
+
+// Variações aceitas: 2025-05-10T14:22:01[.fff][Z|-03:00], 2025-05-10 14:22[:01], 2025-05-10[Z|-03:00],
+// 10/05/2025[ 14:22:01], 20250510, 202505[Z], 2025-05 e 05/2025 (competência: primeiro dia do mês)
+pub(crate) fn parse(text: &str) -> Option<ParsedDate> {
+    let text = text.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(ParsedDate { local: dt.naive_local(), offset: Some(*dt.offset()) });
+    }
+    for formato in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M", "%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M"] {
+        if let Ok(local) = NaiveDateTime::parse_from_str(text, formato) {
+            return Some(ParsedDate { local, offset: None });
+        }
+    }
+
+    // Só a data, com fuso opcional (xs:date: 2025-05-01Z, 2025-05-01-03:00)
+    if text.len() >= 10 && text.is_char_boundary(10) {
+        let (data, fuso) = text.split_at(10);
+        if let Ok(data) = NaiveDate::parse_from_str(data, "%Y-%m-%d") {
+            // O que vem depois da data e não é fuso fica para os outros formatos
+            let offset = parse_offset(fuso);
+            if fuso.is_empty() || offset.is_some() {
+                return Some(ParsedDate { local: data.and_time(NaiveTime::MIN), offset });
+            }
+        }
+    }
+    for formato in ["%d/%m/%Y", "%Y%m%d"] {
+        if let Ok(data) = NaiveDate::parse_from_str(text, formato) {
+            return Some(ParsedDate { local: data.and_time(NaiveTime::MIN), offset: None });
+        }
+    }
+
+    // Competência: 202505, 202505Z, 2025-05, 05/2025
+    let competencia = text.strip_suffix('Z').unwrap_or(text);
+    if competencia.len() == 6 && competencia.chars().all(|c| c.is_ascii_digit()) {
+        return first_day(&competencia[..4], &competencia[4..]).map(|local| ParsedDate { local, offset: None });
+    }
+    if let Some((mes, ano)) = text.split_once('/') {
+        if mes.len() == 2 && ano.len() == 4 {
+            return first_day(ano, mes).map(|local| ParsedDate { local, offset: None });
+        }
+    }
+    if let Some((ano, mes)) = text.split_once('-') {
+        if ano.len() == 4 && mes.len() == 2 {
+            return first_day(ano, mes).map(|local| ParsedDate { local, offset: None });
+        }
+    }
+    None
+}
+
+// Formato strftime válido (evita pânico do chrono ao formatar)
+fn validate_format(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Formato de data inválido: {}", format));
+    }
+    Ok(())
+}
+
+impl DateSettings {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        validate_format(&self.format)?;
+        parse_offset(&self.assumed_offset).ok_or_else(|| format!("Fuso inválido: {}", self.assumed_offset))?;
+        if let Some(timezone) = &self.timezone {
+            parse_offset(timezone).ok_or_else(|| format!("Fuso inválido: {}", timezone))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn format_date(&self, date: ParsedDate, convert_timezone: bool) -> Result<String, String> {
+        let assumido = parse_offset(&self.assumed_offset).ok_or_else(|| format!("Fuso inválido: {}", self.assumed_offset))?;
+        let offset = date.offset.unwrap_or(assumido);
+        let mut data = date
+            .local
+            .and_local_timezone(offset)
+            .single()
+            .ok_or("Data fora do intervalo suportado")?;
+
+        if let Some(timezone) = self.timezone.as_ref().filter(|_| convert_timezone) {
+            let destino = parse_offset(timezone).ok_or_else(|| format!("Fuso inválido: {}", timezone))?;
+            data = data.with_timezone(&destino);
+        }
+
+        let mut texto = String::new();
+        write!(texto, "{}", data.format_with_items(StrftimeItems::new(&self.format)))
+            .map_err(|_| format!("Formato de data inválido: {}", self.format))?;
+        Ok(texto)
+    }
+
+    // Normalizar as datas da nota; as que não forem reconhecidas ficam como vieram, com aviso
+    pub(crate) fn normalize_nota(&self, nota: &mut Value, report: &mut NotaReport) -> Result<(), String> {
+        for &(campo, converter_fuso) in CAMPOS_DATA {
+            let Some(original) = text_at(nota, campo).filter(|s| !s.trim().is_empty()) else {
+                continue;
+            };
+            let Some(data) = parse(&original) else {
+                report.warn(format!("{} em formato não reconhecido: {}", campo, original));
+                continue;
+            };
+
+            let normalizada = self.format_date(data, converter_fuso)?;
+            if normalizada != original {
+                let valor = Value::String(normalizada);
+                report.coerced(campo, &original, &valor);
+                if let Some(campos) = nota.as_object_mut() {
+                    set_path(campos, campo, valor);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use chrono::FixedOffset;
+
+    fn local(text: &str) -> String {
+        parse(text).expect("data não reconhecida").local.format("%Y-%m-%dT%H:%M:%S").to_string()
+    }
+
+    fn offset(text: &str) -> Option<FixedOffset> {
+        parse(text).expect("data não reconhecida").offset
+    }
+
+    #[test]
+    fn parses_provider_variants() {
+        assert_eq!(local("2025-05-10"), "2025-05-10T00:00:00");
+        assert_eq!(offset("2025-05-10"), None);
+
+        assert_eq!(local("2025-05-10T14:22:01-03:00"), "2025-05-10T14:22:01");
+        assert_eq!(offset("2025-05-10T14:22:01-03:00"), FixedOffset::west_opt(3 * 3600));
+
+        assert_eq!(local("2025-05-10 14:22"), "2025-05-10T14:22:00");
+        assert_eq!(local("2025-05-10T14:22"), "2025-05-10T14:22:00");
+        assert_eq!(local("10/05/2025"), "2025-05-10T00:00:00");
+        assert_eq!(local("202505"), "2025-05-01T00:00:00");
+
+        assert_eq!(local("2025-05-01Z"), "2025-05-01T00:00:00");
+        assert_eq!(offset("2025-05-01Z"), FixedOffset::east_opt(0));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(parse("2025-13-10"), None);
+        assert_eq!(parse("2025-05-10 lixo"), None);
+        assert_eq!(parse("30/02/2025"), None);
+    }
+}
@@ -0,0 +1,113 @@
+// CNPJ, CPF e inscrição municipal: remoção de máscara, zeros à esquerda e dígitos
+// verificadores. O CNPJ aceita o formato alfanumérico (12 posições [0-9A-Z] + 2 dígitos).
+// No modelo interno o campo Cnpj guarda CNPJ ou CPF, como fazem os adaptadores.
+use serde_json::Value;
+
+use crate::nota::{set_path, text_at};
+use crate::report::NotaReport;
+
+// Campos de documento por participante: (caminho do CNPJ/CPF, caminho da inscrição, nome)
+const PARTICIPANTES: &[(&str, &str, &str)] = &[
+    ("PrestadorServico/IdentificacaoPrestador/Cnpj", "PrestadorServico/IdentificacaoPrestador/InscricaoMunicipal", "prestador"),
+    ("TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj", "TomadorServico/IdentificacaoTomador/InscricaoMunicipal", "tomador"),
+    ("IntermediarioServico/CpfCnpj/Cnpj", "IntermediarioServico/InscricaoMunicipal", "intermediário"),
+];
+
+pub(crate) fn strip_mask(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+// Dígito módulo 11 com pesos de 2 até `peso_maximo`, da direita para a esquerda. O valor de
+// cada posição é o código ASCII - 48 (dígitos 0-9, letras A=17...).
+fn dv_modulo_11(base: &[u8], peso_maximo: u32) -> u8 {
+    let soma: u32 = base
+        .iter()
+        .rev()
+        .zip((2..=peso_maximo).cycle())
+        .map(|(c, peso)| u32::from(c - b'0') * peso)
+        .sum();
+    match soma % 11 {
+        0 | 1 => 0,
+        resto => (11 - resto) as u8,
+    }
+}
+
+fn all_same(id: &str) -> bool {
+    id.chars().all(|c| id.starts_with(c))
+}
+
+pub(crate) fn is_valid_cnpj(cnpj: &str) -> bool {
+    let bytes = cnpj.as_bytes();
+    if bytes.len() != 14
+        || !bytes[..12].iter().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+        || !bytes[12..].iter().all(u8::is_ascii_digit)
+        || all_same(cnpj)
+    {
+        return false;
+    }
+    let dv1 = dv_modulo_11(&bytes[..12], 9);
+    let dv2 = dv_modulo_11(&bytes[..13], 9);
+    bytes[12] - b'0' == dv1 && bytes[13] - b'0' == dv2
+}
+
+pub(crate) fn is_valid_cpf(cpf: &str) -> bool {
+    let bytes = cpf.as_bytes();
+    if bytes.len() != 11 || !bytes.iter().all(u8::is_ascii_digit) || all_same(cpf) {
+        return false;
+    }
+    // No CPF os pesos não reiniciam em 2 (10..2 e 11..2)
+    let dv1 = dv_modulo_11(&bytes[..9], 11);
+    let dv2 = dv_modulo_11(&bytes[..10], 11);
+    bytes[9] - b'0' == dv1 && bytes[10] - b'0' == dv2
+}
+
+// Normalizar um CNPJ ou CPF; o booleano diz se os dígitos verificadores conferem.
+// Até 11 posições vale como CPF se conferir; senão completa como CNPJ.
+pub(crate) fn normalize(text: &str) -> (String, bool) {
+    let id = strip_mask(text);
+    if id.len() <= 11 {
+        let cpf = format!("{:0>11}", id);
+        if is_valid_cpf(&cpf) {
+            return (cpf, true);
+        }
+    }
+    let cnpj = format!("{:0>14}", id);
+    if is_valid_cnpj(&cnpj) {
+        return (cnpj, true);
+    }
+    // Inválido: mantém sem máscara, completando conforme o tamanho
+    let largura = if id.len() <= 11 { 11 } else { 14 };
+    (format!("{:0>width$}", id, width = largura), false)
+}
+
+fn update(nota: &mut Value, path: &str, original: &str, normalizado: String, report: &mut NotaReport) {
+    if normalizado != original {
+        let valor = Value::String(normalizado);
+        report.coerced(path, original, &valor);
+        if let Some(campos) = nota.as_object_mut() {
+            set_path(campos, path, valor);
+        }
+    }
+}
+
+// Normalizar os documentos de prestador, tomador e intermediário, avisando dos inválidos
+pub(crate) fn normalize_nota(nota: &mut Value, report: &mut NotaReport) {
+    for (documento_path, inscricao_path, participante) in PARTICIPANTES {
+        if let Some(original) = text_at(nota, documento_path).filter(|s| !s.trim().is_empty()) {
+            let (normalizado, valido) = normalize(&original);
+            // Zeros indicam documento não informado (valor padrão), não um documento inválido
+            if !valido && !normalizado.chars().all(|c| c == '0') {
+                report.warn(format!("CNPJ/CPF do {} inválido: {}", participante, original));
+            }
+            update(nota, documento_path, &original, normalizado, report);
+        }
+
+        if let Some(original) = text_at(nota, inscricao_path).filter(|s| !s.trim().is_empty()) {
+            let normalizada = strip_mask(&original);
+            update(nota, inscricao_path, &original, normalizada, report);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+// Filtro das notas antes da exportação: período de emissão e de competência, prestadores,
+// tomadores, ISS retido, item da lista de serviços e faixa de ValorServicos. Critério vazio
+// não filtra; os critérios informados valem todos juntos.
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::dates;
+use crate::document_id;
+use crate::nota::{number_at, text_at};
+use crate::services::ServiceCode;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct NotaFilter {
+    // Datas inclusivas, em qualquer formato aceito em dates::parse
+    pub(crate) emissao_from: Option<String>,
+    pub(crate) emissao_to: Option<String>,
+    // Meses inclusivos (2025-05, 05/2025, 202505)
+    pub(crate) competencia_from: Option<String>,
+    pub(crate) competencia_to: Option<String>,
+    pub(crate) prestadores: Vec<String>,
+    pub(crate) tomadores: Vec<String>,
+    pub(crate) iss_retido: Option<bool>,
+    pub(crate) itens: Vec<String>,
+    pub(crate) valor_min: Option<f64>,
+    pub(crate) valor_max: Option<f64>,
+}
+
+// Critérios já interpretados, para não reinterpretar a cada nota
+struct Criterios {
+    emissao: (Option<NaiveDate>, Option<NaiveDate>),
+    competencia: (Option<NaiveDate>, Option<NaiveDate>),
+    prestadores: Vec<String>,
+    tomadores: Vec<String>,
+    itens: Vec<(u32, u32)>,
+}
+
+fn parse_date(campo: &str, texto: &Option<String>) -> Result<Option<NaiveDate>, String> {
+    match texto.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(texto) => dates::parse(texto)
+            .map(|data| Some(data.local.date()))
+            .ok_or_else(|| format!("Filtro: {} inválida: {}", campo, texto)),
+        None => Ok(None),
+    }
+}
+
+// Competência compara só o mês
+fn month_start(data: NaiveDate) -> NaiveDate {
+    data.with_day0(0).unwrap_or(data)
+}
+
+fn in_range(data: Option<NaiveDate>, (inicio, fim): (Option<NaiveDate>, Option<NaiveDate>)) -> bool {
+    if inicio.is_none() && fim.is_none() {
+        return true;
+    }
+    // Sem data na nota, o período não tem como ser conferido
+    let Some(data) = data else {
+        return false;
+    };
+    inicio.is_none_or(|inicio| data >= inicio) && fim.is_none_or(|fim| data <= fim)
+}
+
+fn nota_date(nota: &Value, campo: &str) -> Option<NaiveDate> {
+    text_at(nota, campo).and_then(|texto| dates::parse(&texto)).map(|data| data.local.date())
+}
+
+fn matches_document(lista: &[String], nota: &Value, path: &str) -> bool {
+    lista.is_empty() || text_at(nota, path).is_some_and(|documento| lista.contains(&document_id::normalize(&documento).0))
+}
+
+impl NotaFilter {
+    fn criterios(&self) -> Result<Criterios, String> {
+        let item = |texto: &String| {
+            ServiceCode::parse(texto)
+                .map(|codigo| (codigo.item, codigo.subitem))
+                .ok_or_else(|| format!("Filtro: item da lista de serviços inválido: {}", texto))
+        };
+        Ok(Criterios {
+            emissao: (parse_date("data inicial", &self.emissao_from)?, parse_date("data final", &self.emissao_to)?),
+            competencia: (
+                parse_date("competência inicial", &self.competencia_from)?.map(month_start),
+                parse_date("competência final", &self.competencia_to)?.map(month_start),
+            ),
+            prestadores: self.prestadores.iter().map(|d| document_id::normalize(d).0).collect(),
+            tomadores: self.tomadores.iter().map(|d| document_id::normalize(d).0).collect(),
+            itens: self.itens.iter().map(item).collect::<Result<_, _>>()?,
+        })
+    }
+
+    // Separar as posições das notas que passam no filtro
+    pub(crate) fn select(&self, notas: &[Value]) -> Result<Vec<bool>, String> {
+        let criterios = self.criterios()?;
+        Ok(notas.iter().map(|nota| self.accepts(&criterios, nota)).collect())
+    }
+
+    fn accepts(&self, criterios: &Criterios, nota: &Value) -> bool {
+        let valor = number_at(nota, "Servico/Valores/ValorServicos").unwrap_or(0.0);
+        let retido = text_at(nota, "Servico/Valores/IssRetido").as_deref() == Some("1");
+        let item = text_at(nota, "Servico/ItemListaServico")
+            .and_then(|texto| ServiceCode::parse(&texto))
+            .map(|codigo| (codigo.item, codigo.subitem));
+
+        in_range(nota_date(nota, "DataEmissao"), criterios.emissao)
+            && in_range(nota_date(nota, "Competencia").map(month_start), criterios.competencia)
+            && matches_document(&criterios.prestadores, nota, "PrestadorServico/IdentificacaoPrestador/Cnpj")
+            && matches_document(&criterios.tomadores, nota, "TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj")
+            && self.iss_retido.is_none_or(|filtro| filtro == retido)
+            && (criterios.itens.is_empty() || item.is_some_and(|item| criterios.itens.contains(&item)))
+            && self.valor_min.is_none_or(|minimo| valor >= minimo)
+            && self.valor_max.is_none_or(|maximo| valor <= maximo)
+    }
+}
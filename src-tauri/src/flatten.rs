@@ -0,0 +1,162 @@
+// Achatamento genérico de XML em CSV (catálogos de fornecedores, extratos de banco): cada
+// ocorrência do caminho de registro vira uma linha e cada caminho dentro do registro vira uma
+// coluna ("Preco/@moeda", "Fornecedor/Nome"). Sem colunas informadas, elas são descobertas em
+// todos os registros, na ordem em que aparecem. Filhos repetidos são juntados numa célula.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::writers::CsvSettings;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct FlattenOptions {
+    // Caminho do elemento que se repete, a partir da raiz (ex.: "/Catalogo/Item")
+    pub(crate) record_path: String,
+    // Colunas escolhidas, relativas ao registro; vazio descobre todas
+    pub(crate) columns: Vec<String>,
+    // Separador entre os valores de filhos repetidos na mesma célula
+    pub(crate) join_separator: String,
+    pub(crate) csv: CsvSettings,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        FlattenOptions { record_path: String::new(), columns: Vec::new(), join_separator: " | ".to_string(), csv: CsvSettings::default() }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FlattenResult {
+    pub(crate) saved_path: String,
+    pub(crate) records: usize,
+    pub(crate) columns: Vec<String>,
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/').map(str::trim).filter(|segment| !segment.is_empty() && *segment != ".").collect()
+}
+
+// Chave na árvore do parse_xml_to_json: atributos ganham "@_"
+fn tree_key(segment: &str) -> String {
+    match segment.strip_prefix('@') {
+        Some(nome) => format!("@_{}", nome),
+        None => segment.to_string(),
+    }
+}
+
+// Todos os valores no caminho, descendo por cada item das listas (elementos repetidos)
+fn collect<'a>(value: &'a Value, caminho: &[&str], valores: &mut Vec<&'a Value>) {
+    if let Value::Array(lista) = value {
+        for item in lista {
+            collect(item, caminho, valores);
+        }
+        return;
+    }
+    match caminho.split_first() {
+        None => valores.push(value),
+        Some((segmento, resto)) => {
+            if let Some(filho) = value.get(tree_key(segmento)) {
+                collect(filho, resto, valores);
+            }
+        }
+    }
+}
+
+// Texto de uma folha; elemento com atributos contribui com o texto guardado em "#text"
+fn leaf_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Object(map) => map.get("#text").and_then(leaf_text),
+        _ => None,
+    }
+}
+
+fn cell(record: &Value, coluna: &str, separador: &str) -> String {
+    let mut valores = Vec::new();
+    collect(record, &segments(coluna), &mut valores);
+    valores.into_iter().filter_map(leaf_text).collect::<Vec<_>>().join(separador)
+}
+
+// Caminhos de todas as folhas do registro, sem repetir
+fn discover(value: &Value, prefixo: &str, colunas: &mut Vec<String>, vistas: &mut HashSet<String>) {
+    let mut adicionar = |coluna: String| {
+        if vistas.insert(coluna.clone()) {
+            colunas.push(coluna);
+        }
+    };
+    match value {
+        Value::Array(lista) => {
+            for item in lista {
+                discover(item, prefixo, colunas, vistas);
+            }
+        }
+        Value::Object(map) => {
+            if map.contains_key("#text") {
+                adicionar(if prefixo.is_empty() { ".".to_string() } else { prefixo.to_string() });
+            }
+            for (chave, filho) in map {
+                if chave == "#text" {
+                    continue;
+                }
+                let nome = match chave.strip_prefix("@_") {
+                    Some(atributo) => format!("@{}", atributo),
+                    None => chave.clone(),
+                };
+                let caminho = if prefixo.is_empty() { nome } else { format!("{}/{}", prefixo, nome) };
+                discover(filho, &caminho, colunas, vistas);
+            }
+        }
+        Value::Null => {}
+        _ => adicionar(if prefixo.is_empty() { ".".to_string() } else { prefixo.to_string() }),
+    }
+}
+
+// Registros no caminho pedido; o primeiro segmento é o elemento raiz do documento
+fn records<'a>(tree: &'a Value, record_path: &str) -> Result<Vec<&'a Value>, String> {
+    let caminho = segments(record_path);
+    let Some((raiz, resto)) = caminho.split_first() else {
+        return Err("Informe o caminho dos registros (ex.: /Catalogo/Item)".to_string());
+    };
+    let Some(documento) = tree.get(*raiz) else {
+        let encontrada = tree.as_object().and_then(|map| map.keys().next().cloned()).unwrap_or_default();
+        return Err(format!("O documento começa em <{}>, não em <{}>", encontrada, raiz));
+    };
+    let mut registros = Vec::new();
+    collect(documento, resto, &mut registros);
+    Ok(registros)
+}
+
+// Montar o CSV; devolve o conteúdo e as colunas usadas
+pub(crate) fn to_csv(trees: &[Value], options: &FlattenOptions) -> Result<(String, usize, Vec<String>), String> {
+    // Os valores saem como estão no XML; a vírgula decimal não se aplica
+    CsvSettings { decimal_comma: false, ..options.csv.clone() }.validate()?;
+    let mut registros = Vec::new();
+    for tree in trees {
+        registros.extend(records(tree, &options.record_path)?);
+    }
+    if registros.is_empty() {
+        return Err(format!("Nenhum registro encontrado em {}", options.record_path));
+    }
+
+    let colunas = if options.columns.is_empty() {
+        let (mut colunas, mut vistas) = (Vec::new(), HashSet::new());
+        for registro in &registros {
+            discover(registro, "", &mut colunas, &mut vistas);
+        }
+        colunas
+    } else {
+        options.columns.iter().map(|coluna| coluna.trim().to_string()).filter(|coluna| !coluna.is_empty()).collect()
+    };
+
+    let separador = options.csv.delimiter.to_string();
+    let mut linhas = vec![colunas.iter().map(|coluna| options.csv.field(coluna)).collect::<Vec<_>>().join(&separador)];
+    for registro in &registros {
+        let campos: Vec<String> = colunas.iter().map(|coluna| options.csv.field(&cell(registro, coluna, &options.join_separator))).collect();
+        linhas.push(campos.join(&separador));
+    }
+    Ok((linhas.join("\r\n") + "\r\n", registros.len(), colunas))
+}
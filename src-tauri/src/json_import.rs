@@ -0,0 +1,111 @@
+// Conversão de JSON para XML: correções mantidas em JSON e arquivos vindos de outras
+// ferramentas. As chaves seguem as convenções configuradas (prefixo dos atributos e chave do
+// texto) e são trazidas para as do build_xml_from_json ("@_" e "#text") antes de montar o XML.
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::build_xml;
+use crate::writers::JsonSettings;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct XmlSettings {
+    // Espaços por nível; zero gera o documento numa linha só
+    pub(crate) indent: usize,
+    // Incluir <?xml version="1.0" encoding="UTF-8"?>
+    pub(crate) declaration: bool,
+}
+
+impl Default for XmlSettings {
+    fn default() -> Self {
+        XmlSettings { indent: 2, declaration: true }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct JsonImportOptions {
+    pub(crate) json: JsonSettings,
+    pub(crate) xml: XmlSettings,
+}
+
+// Nome de elemento ou atributo aceito no XML (sem espaços, "<", "&" etc.)
+fn is_valid_name(nome: &str) -> bool {
+    let mut caracteres = nome.chars();
+    caracteres.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && caracteres.all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+fn attribute_value(caminho: &str, valor: Value) -> Result<Value, String> {
+    match valor {
+        Value::String(_) => Ok(valor),
+        Value::Number(n) => Ok(Value::String(n.to_string())),
+        Value::Bool(b) => Ok(Value::String(b.to_string())),
+        Value::Null => Ok(Value::String(String::new())),
+        _ => Err(format!("Atributo {} precisa ser texto, número ou booleano", caminho)),
+    }
+}
+
+// Renomear as chaves para as convenções internas, conferindo os nomes pelo caminho todo
+fn to_internal(valor: Value, caminho: &str, settings: &JsonSettings) -> Result<Value, String> {
+    match valor {
+        Value::Object(map) => {
+            let mut interno = Map::new();
+            for (chave, valor) in map {
+                if chave == settings.text_key {
+                    interno.insert("#text".to_string(), valor);
+                } else if let Some(nome) = chave.strip_prefix(&settings.attribute_prefix) {
+                    let caminho = format!("{}/@{}", caminho, nome);
+                    if !is_valid_name(nome) {
+                        return Err(format!("Nome de atributo inválido no JSON: {}", caminho));
+                    }
+                    interno.insert(format!("@_{}", nome), attribute_value(&caminho, valor)?);
+                } else {
+                    let caminho = format!("{}/{}", caminho, chave);
+                    if !is_valid_name(&chave) {
+                        return Err(format!("Nome de elemento inválido no JSON: {}", caminho));
+                    }
+                    interno.insert(chave, to_internal(valor, &caminho, settings)?);
+                }
+            }
+            Ok(Value::Object(interno))
+        }
+        Value::Array(lista) => lista
+            .into_iter()
+            .map(|item| match item {
+                Value::Array(_) => Err(format!("Lista dentro de lista não tem representação em XML: {}", caminho)),
+                item => to_internal(item, caminho, settings),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        other => Ok(other),
+    }
+}
+
+pub(crate) fn to_xml(content: &str, options: &JsonImportOptions) -> Result<String, String> {
+    options.json.validate()?;
+    let valor: Value = serde_json::from_str(content).map_err(|e| format!("JSON inválido: {}", e))?;
+    let Value::Object(map) = valor else {
+        return Err("O JSON precisa ser um objeto com um único elemento raiz".to_string());
+    };
+
+    // A declaração "?xml" que algumas ferramentas gravam no JSON não conta como raiz
+    let mut raizes: Vec<(String, Value)> = map.into_iter().filter(|(chave, _)| !chave.starts_with('?')).collect();
+    if raizes.is_empty() {
+        return Err("O JSON não tem elemento raiz".to_string());
+    }
+    if raizes.len() > 1 {
+        let nomes: Vec<&str> = raizes.iter().map(|(chave, _)| chave.as_str()).collect();
+        return Err(format!("O JSON precisa ter um único elemento raiz; encontrados {}: {}", raizes.len(), nomes.join(", ")));
+    }
+    let (nome, raiz) = raizes.remove(0);
+    if nome == options.json.text_key || nome.starts_with(&options.json.attribute_prefix) || !is_valid_name(&nome) {
+        return Err(format!("Elemento raiz inválido no JSON: {}", nome));
+    }
+    if raiz.is_array() {
+        return Err(format!("O elemento raiz {} não pode ser uma lista", nome));
+    }
+
+    let raiz = to_internal(raiz, &nome, &options.json)?;
+    Ok(build_xml(&json!({ nome: raiz }), &" ".repeat(options.xml.indent), options.xml.declaration))
+}
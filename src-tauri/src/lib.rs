@@ -1,9 +1,45 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use std::fs;
+use std::path::Path;
 use serde_json::{Value, Map};
-use regex::Regex;
 use rfd::FileDialog;
 
+mod adn;
+mod cancellation;
+mod company;
+mod dates;
+mod document_id;
+mod filter;
+mod flatten;
+mod json_import;
+mod mapping;
+mod merge;
+mod municipalities;
+mod nota;
+mod numbering;
+mod providers;
+mod report;
+mod services;
+mod soap;
+mod split;
+mod summary;
+mod taxes;
+mod writers;
+
+use cancellation::CancelledPolicy;
+use company::{CompanyProfile, CompanyProfiles};
+use filter::NotaFilter;
+use flatten::{FlattenOptions, FlattenResult};
+use json_import::JsonImportOptions;
+use mapping::MappingProfile;
+use merge::MergeEntry;
+use numbering::NumberingReport;
+use providers::Record;
+use report::{ConversionReport, Failure, IgnoredEvent, NotaReport, ReportFormat};
+use split::{SplitGroup, SplitKey};
+use summary::Summary;
+use writers::{CsvSettings, JsonSettings, OutputFormat};
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
@@ -21,7 +57,8 @@ fn parse_xml_to_json(xml_content: &str) -> Result<Value, String> {
         }
         
         // Processar filhos
-        let mut children_map = std::collections::HashMap::new();
+        let mut children_map = Map::new();
+        let mut text = String::new();
         for child in node.children() {
             if child.is_element() {
                 let child_name = child.tag_name().name();
@@ -38,30 +75,51 @@ fn parse_xml_to_json(xml_content: &str) -> Result<Value, String> {
                         }
                     }
                     None => {
-                        children_map.insert(child_name, child_value);
+                        children_map.insert(child_name.to_string(), child_value);
                     }
                 }
             } else if child.is_text() {
-                let text = child.text().unwrap_or("").trim();
-                if !text.is_empty() && map.is_empty() && children_map.is_empty() {
-                    return Value::String(text.to_string());
-                }
+                text.push_str(child.text().unwrap_or("").trim());
             }
         }
         
-        for (key, value) in children_map {
-            map.insert(key.to_string(), value);
+        // Só texto vira string; com atributos ou filhos, o texto fica em "#text"
+        if !text.is_empty() {
+            if map.is_empty() && children_map.is_empty() {
+                return Value::String(text);
+            }
+            map.insert("#text".to_string(), Value::String(text));
         }
+        map.extend(children_map);
         
         Value::Object(map)
     }
     
-    Ok(node_to_value(doc.root_element()))
+    // Manter o elemento raiz como chave (igual ao XMLParser no JS)
+    let root = doc.root_element();
+    let mut tree = Map::new();
+    tree.insert(root.tag_name().name().to_string(), node_to_value(root));
+    Ok(Value::Object(tree))
+}
+
+// Escapar caracteres reservados em textos e atributos
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 fn build_xml_from_json(value: &Value) -> String {
-    fn json_to_xml(val: &Value, tag: &str, depth: usize) -> String {
-        let indent = "  ".repeat(depth);
+    build_xml(value, "  ", true)
+}
+
+// Montar o XML com a indentação pedida (vazia: cada documento numa linha só). A chave
+// "#text" vira o texto de um elemento que também tem atributos.
+fn build_xml(value: &Value, indent_unit: &str, declaration: bool) -> String {
+    fn json_to_xml(val: &Value, tag: &str, depth: usize, indent_unit: &str) -> String {
+        let indent = indent_unit.repeat(depth);
+        let newline = if indent_unit.is_empty() { "" } else { "\n" };
         
         match val {
             Value::Object(map) => {
@@ -70,235 +128,384 @@ fn build_xml_from_json(value: &Value) -> String {
                 
                 // Adicionar atributos primeiro
                 for (key, value) in map.iter() {
-                    if key.starts_with("@_") {
-                        let attr_name = &key[2..];
+                    if let Some(attr_name) = key.strip_prefix("@_") {
                         if let Value::String(attr_value) = value {
-                            xml.push_str(&format!(" {}=\"{}\"", attr_name, attr_value));
+                            xml.push_str(&format!(" {}=\"{}\"", attr_name, escape_xml(attr_value)));
                         }
                     }
                 }
                 xml.push('>');
                 
+                let text = match map.get("#text") {
+                    Some(Value::String(s)) => escape_xml(s),
+                    Some(Value::Number(n)) => n.to_string(),
+                    Some(Value::Bool(b)) => b.to_string(),
+                    _ => String::new(),
+                };
+                
                 // Adicionar elementos filhos
                 for (key, value) in map.iter() {
-                    if !key.starts_with("@_") {
+                    if !key.starts_with("@_") && key != "#text" {
                         match value {
                             Value::Array(arr) => {
                                 for item in arr {
-                                    content.push('\n');
-                                    content.push_str(&json_to_xml(item, key, depth + 1));
+                                    content.push_str(newline);
+                                    content.push_str(&json_to_xml(item, key, depth + 1, indent_unit));
                                 }
                             }
                             _ => {
-                                content.push('\n');
-                                content.push_str(&json_to_xml(value, key, depth + 1));
+                                content.push_str(newline);
+                                content.push_str(&json_to_xml(value, key, depth + 1, indent_unit));
                             }
                         }
                     }
                 }
                 
+                xml.push_str(&text);
                 if !content.is_empty() {
                     xml.push_str(&content);
-                    xml.push('\n');
+                    xml.push_str(newline);
                     xml.push_str(&indent);
                 }
                 xml.push_str(&format!("</{}>", tag));
                 xml
             }
             Value::String(s) => {
-                format!("{}<{}>{}</{}>", indent, tag, s, tag)
+                format!("{}<{}>{}</{}>", indent, tag, escape_xml(s), tag)
             }
             Value::Number(n) => {
                 format!("{}<{}>{}</{}>", indent, tag, n, tag)
             }
+            Value::Bool(b) => {
+                format!("{}<{}>{}</{}>", indent, tag, b, tag)
+            }
             _ => format!("{}<{}/>{}", indent, tag, "")
         }
     }
     
-    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut xml = if declaration { String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n") } else { String::new() };
     if let Value::Object(map) = value {
         for (key, value) in map.iter() {
-            xml.push_str(&json_to_xml(value, key, 0));
+            xml.push_str(&json_to_xml(value, key, 0, indent_unit));
             xml.push('\n');
         }
     }
     xml
 }
 
-#[tauri::command]
-fn convert_and_save_xml(input_path: String, save_path: String) -> Result<String, String> {
-    // Ler o arquivo XML
-    let xml_content = fs::read_to_string(&input_path).map_err(|e| e.to_string())?;
-    
-    // Parse XML para JSON-like structure
-    let json = parse_xml_to_json(&xml_content)?;
-    
-    // Navegar para ConsultarNfseResposta -> ListaNfse -> CompNfse
-    let lista_nfse = json
-        .get("ConsultarNfseResposta")
-        .and_then(|v| v.get("ListaNfse"))
-        .and_then(|v| v.get("CompNfse"))
-        .ok_or("Não foram encontradas notas fiscais no XML")?;
-    
-    // Converter para array se for um único item (igual ao JS: Array.isArray(listaNfse) ? listaNfse : [listaNfse])
-    let notas = match lista_nfse {
-        Value::Array(arr) => arr.clone(),
-        single => vec![single.clone()],
+// Etapa de decodificação da entrada: pacotes JSON do ADN e envelopes SOAP
+// podem trazer vários documentos XML; os pacotes do ADN trazem também os eventos
+fn read_documents(content: &str) -> Result<(Vec<String>, Vec<adn::Event>), String> {
+    let pacote = if adn::is_package(content) {
+        adn::decode_package(content)?
+    } else {
+        adn::Package { documents: vec![content.to_string()], events: Vec::new() }
     };
-    
-    let mut nfse_list = Vec::new();
-    
-    for comp in notas {
-        let inf = comp.get("Nfse")
-            .and_then(|v| v.get("InfNfse"))
-            .ok_or("Estrutura InfNfse não encontrada")?;
-        
-        let empty_object = Value::Object(Map::new());
-        let tomador = inf.get("TomadorServico").unwrap_or(&empty_object);
-        let prestador = inf.get("PrestadorServico").unwrap_or(&empty_object);
-        let servico = inf.get("Servico").unwrap_or(&empty_object);
-        
-        // Corrigir o número da nota para garantir que seja capturado corretamente (igual ao JS)
-        let numero_nota = match inf.get("Numero") {
-            Some(Value::String(n)) => format!("{:0>8}", n),
-            Some(Value::Number(n)) => format!("{:0>8}", n.as_u64().unwrap_or(0)),
-            _ => "00000000".to_string(),
+
+    let mut documentos = Vec::new();
+    for arquivo in pacote.documents {
+        documentos.extend(soap::unwrap_payloads(&arquivo)?);
+    }
+    Ok((documentos, pacote.events))
+}
+
+// Opções dos comandos de conversão, enviadas pelo frontend num único objeto
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ConversionOptions {
+    output_format: OutputFormat,
+    // Perfil de mapeamento carregado do disco no lugar da detecção de provedor
+    profile_path: Option<String>,
+    // Gravar o relatório de conversão ao lado da saída
+    report_format: Option<ReportFormat>,
+    // Converter as notas válidas mesmo que outras falhem
+    partial: bool,
+    // No modo parcial, gravar as notas rejeitadas num XML de quarentena
+    quarantine: bool,
+    // Destino das notas canceladas ou substituídas
+    cancelled: CancelledPolicy,
+    // Seleção das notas exportadas
+    filter: NotaFilter,
+    // Dividir a saída num arquivo por grupo de notas
+    split: Option<SplitKey>,
+    // Nome dos arquivos divididos (padrão "{nome}-{grupo}")
+    split_template: Option<String>,
+    // Gravar o resumo de totais ao lado da saída
+    summary_format: Option<ReportFormat>,
+    // Delimitador e separador decimal da saída em CSV
+    csv: CsvSettings,
+    // Indentação e convenções de atributos e listas da saída em JSON
+    json: JsonSettings,
+}
+
+// Notas convertidas, o relatório do que foi preenchido, descartado ou reformatado, no
+// modo parcial os registros rejeitados e as notas canceladas separadas das ativas
+#[derive(Default)]
+struct Conversion {
+    notas: Vec<Value>,
+    report: ConversionReport,
+    rejected: Vec<Record>,
+    cancelled: Vec<Value>,
+    summary: Summary,
+    // Eventos do ADN, aplicados no lote todo (o evento pode vir num pacote e a nota em outro)
+    events: Vec<adn::Event>,
+}
+
+impl Conversion {
+    // Etapas comuns a todos os comandos depois da extração: ligar as substituições do lote,
+    // filtrar e aplicar a política de canceladas
+    fn finish(&mut self, options: &ConversionOptions) -> Result<(), String> {
+        self.apply_events();
+        cancellation::link(&mut self.notas, &mut self.report.notas);
+        self.apply_filter(&options.filter)?;
+        writers::check(options.output_format, &self.notas, &mut self.report.notas);
+        self.separate_cancelled(options.cancelled);
+        self.summary = Summary::from_notas(&self.notas);
+        Ok(())
+    }
+
+    // Cancelamentos e substituições do ADN; os demais eventos e os de notas fora do lote
+    // ficam registrados no relatório
+    fn apply_events(&mut self) {
+        for evento in std::mem::take(&mut self.events) {
+            let motivo = match &evento.status {
+                None => "evento não altera a situação da nota",
+                Some(status) if !cancellation::apply_event(&evento.chave, status, &mut self.notas, &mut self.report.notas) => "nota não está neste lote",
+                Some(_) => continue,
+            };
+            self.report.ignored_events.push(IgnoredEvent { chave: evento.chave, event: evento.description, reason: motivo.to_string() });
+        }
+    }
+
+    // As notas fora do filtro saem também do relatório; fica só a contagem
+    fn apply_filter(&mut self, filter: &NotaFilter) -> Result<(), String> {
+        let selecionadas = filter.select(&self.notas)?;
+        let notas = std::mem::take(&mut self.notas).into_iter().zip(std::mem::take(&mut self.report.notas));
+        for ((nota, report), selecionada) in notas.zip(selecionadas) {
+            if selecionada {
+                self.notas.push(nota);
+                self.report.notas.push(report);
+            } else {
+                self.report.filtered_out += 1;
+            }
+        }
+        Ok(())
+    }
+
+    // Política de canceladas; os relatórios de todas as notas continuam no relatório
+    fn separate_cancelled(&mut self, policy: CancelledPolicy) {
+        if policy == CancelledPolicy::Flag {
+            return;
+        }
+        let (ativas, canceladas): (Vec<_>, Vec<_>) = std::mem::take(&mut self.notas)
+            .into_iter()
+            .zip(&self.report.notas)
+            .partition(|(_, report)| report.status.is_active());
+        self.notas = ativas.into_iter().map(|(nota, _)| nota).collect();
+        if policy == CancelledPolicy::Separate {
+            self.cancelled = canceladas.into_iter().map(|(nota, _)| nota).collect();
+        }
+    }
+}
+
+// Resultado devolvido ao frontend pelos comandos de conversão
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConversionResult {
+    saved_path: String,
+    report_path: Option<String>,
+    quarantine_path: Option<String>,
+    cancelled_path: Option<String>,
+    summary_path: Option<String>,
+    report: ConversionReport,
+    summary: Summary,
+}
+
+// Converter todos os documentos da entrada num único lote de notas, completando os campos
+// ausentes com os valores do perfil de empresa. Com `partial`, notas com problema vão para
+// a lista de falhas e as demais seguem; sem ele, a primeira falha interrompe a conversão.
+fn extract_notas(content: &str, profile: Option<&MappingProfile>, company: &CompanyProfile, partial: bool) -> Result<Conversion, String> {
+    // Sem perfil informado, os adaptadores de provedor entregam InfNfse ABRASF para o perfil embutido
+    let mapeamento = profile.unwrap_or_else(|| mapping::default_profile());
+    let mut conversion = Conversion::default();
+    let mut posicao = 0;
+
+    let (documentos, eventos) = read_documents(content)?;
+    conversion.events = eventos;
+    for documento in documentos {
+        let records = match extract_document(&documento, profile) {
+            Ok(records) => records,
+            Err(reason) if partial => {
+                conversion.report.failures.push(Failure { index: None, numero: None, reason });
+                continue;
+            }
+            Err(reason) => return Err(reason),
         };
-        
-        // Garantir que a DataEmissao esteja no formato correto (igual ao JS)
-        let data_emissao = inf.get("DataEmissao")
-            .and_then(|v| v.as_str())
-            .unwrap_or("2025-01-01T00:00:00")
-            .to_string();
-        
-        // Corrigir o formato da Competencia para "YYYY-MM-DDTHH:mm:ss" (igual ao JS)
-        let competencia = match inf.get("Competencia").and_then(|v| v.as_str()) {
-            Some(comp) => {
-                let re = Regex::new(r"^\d{6}Z$").unwrap();
-                if re.is_match(comp) {
-                    // Transformar "202505Z" em "2025-05-01T00:00:00" (igual ao JS)
-                    let year = &comp[0..4];
-                    let month = &comp[4..6];
-                    format!("{}-{}-01T00:00:00", year, month)
-                } else {
-                    comp.to_string()
+
+        for mut record in records {
+            posicao += 1;
+            let mut report = NotaReport::default();
+            let convertida = match &record.inf {
+                Ok(inf) => {
+                    let mut nota = mapeamento.map_record(inf, &mut report);
+                    company.services.fill_from_cnae(&mut nota, &mut report);
+                    company
+                        .apply(&mut nota, &mut report)
+                        .and_then(|_| company.dates.normalize_nota(&mut nota, &mut report))
+                        .map(|_| {
+                            company.services.normalize_nota(&mut nota, &mut report);
+                            company.municipalities.normalize_nota(&mut nota, &mut report);
+                            document_id::normalize_nota(&mut nota, &mut report);
+                            company.taxes.check_nota(&nota, &mut report);
+                            cancellation::apply(&record.status, &mut nota, &mut report);
+                            nota
+                        })
+                }
+                Err(reason) => Err(reason.clone()),
+            };
+
+            match convertida {
+                Ok(nota) => {
+                    conversion.notas.push(nota);
+                    conversion.report.notas.push(report);
                 }
+                Err(reason) if partial => {
+                    conversion.report.failures.push(Failure { index: Some(posicao), numero: record.numero(), reason: reason.clone() });
+                    record.inf = Err(reason);
+                    conversion.rejected.push(record);
+                }
+                Err(reason) => return Err(reason),
             }
-            None => "2025-01-01T00:00:00".to_string(),
-        };
-        
-        // Garantir que o ItemListaServico seja válido (igual ao JS)
-        let item_lista_servico = servico.get("ItemListaServico")
-            .and_then(|v| v.as_str())
-            .unwrap_or("1005")
-            .to_string();
-        
-        // Definir CNPJ genérico padrão para CpfCnpj (igual ao JS)
-        let cnpj_padrao = "00000000000000";
-        
-        // Criar estrutura de saída seguindo EXATAMENTE o padrão do conversor.js
-        let nfse_output = serde_json::json!({
-            "InfNfse": {
-                "@_Id": inf.get("@_id").or(inf.get("id")).or(inf.get("Id")).and_then(|v| v.as_str()).unwrap_or(""),
-                "Numero": numero_nota,
-                "CodigoVerificacao": inf.get("CodigoVerificacao").and_then(|v| v.as_str()).unwrap_or(""),
-                "DataEmissao": data_emissao,
-                "NaturezaOperacao": inf.get("NaturezaOperacao"),
-                "OptanteSimplesNacional": inf.get("OptanteSimplesNacional"),
-                "IncentivadorCultural": inf.get("IncentivadorCultural"),
-                "Competencia": competencia,
-                "Servico": {
-                    "Valores": {
-                        "ValorServicos": servico.get("Valores").and_then(|v| v.get("ValorServicos")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorDeducoes": servico.get("Valores").and_then(|v| v.get("ValorDeducoes")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorPis": servico.get("Valores").and_then(|v| v.get("ValorPis")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorCofins": servico.get("Valores").and_then(|v| v.get("ValorCofins")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorInss": servico.get("Valores").and_then(|v| v.get("ValorInss")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorIr": servico.get("Valores").and_then(|v| v.get("ValorIr")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorCsll": servico.get("Valores").and_then(|v| v.get("ValorCsll")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "IssRetido": servico.get("Valores").and_then(|v| v.get("IssRetido")).and_then(|v| v.as_i64()).unwrap_or(0),
-                        "ValorIss": servico.get("Valores").and_then(|v| v.get("ValorIss")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "OutrasRetencoes": servico.get("Valores").and_then(|v| v.get("OutrasRetencoes")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "BaseCalculo": servico.get("Valores").and_then(|v| v.get("BaseCalculo")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "Aliquota": servico.get("Valores").and_then(|v| v.get("Aliquota")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "ValorLiquidoNfse": servico.get("Valores").and_then(|v| v.get("ValorLiquidoNfse")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "DescontoIncondicionado": servico.get("Valores").and_then(|v| v.get("DescontoIncondicionado")).and_then(|v| v.as_f64()).unwrap_or(0.0),
-                        "DescontoCondicionado": servico.get("Valores").and_then(|v| v.get("DescontoCondicionado")).and_then(|v| v.as_f64()).unwrap_or(0.0)
-                    },
-                    "ItemListaServico": item_lista_servico,
-                    "CodigoCnae": servico.get("CodigoCnae").and_then(|v| v.as_str()).unwrap_or(""),
-                    "Discriminacao": servico.get("Discriminacao").and_then(|v| v.as_str()).unwrap_or(""),
-                    "CodigoMunicipio": servico.get("CodigoMunicipio").and_then(|v| v.as_str()).unwrap_or("2602902")
-                },
-                "PrestadorServico": {
-                    "IdentificacaoPrestador": {
-                        "Cnpj": prestador.get("IdentificacaoPrestador").and_then(|v| v.get("Cnpj")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "InscricaoMunicipal": prestador.get("IdentificacaoPrestador").and_then(|v| v.get("InscricaoMunicipal")).and_then(|v| v.as_str()).unwrap_or("")
-                    },
-                    "RazaoSocial": prestador.get("RazaoSocial").and_then(|v| v.as_str()).unwrap_or(""),
-                    "Endereco": {
-                        "Endereco": prestador.get("Endereco").and_then(|v| v.get("Endereco")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Numero": prestador.get("Endereco").and_then(|v| v.get("Numero")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Bairro": prestador.get("Endereco").and_then(|v| v.get("Bairro")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "CodigoMunicipio": prestador.get("Endereco").and_then(|v| v.get("CodigoMunicipio")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Uf": prestador.get("Endereco").and_then(|v| v.get("Uf")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Cep": prestador.get("Endereco").and_then(|v| v.get("Cep")).and_then(|v| v.as_str()).unwrap_or("")
-                    },
-                    "Contato": {
-                        "Telefone": prestador.get("Contato").and_then(|v| v.get("Telefone")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Email": prestador.get("Contato").and_then(|v| v.get("Email")).and_then(|v| v.as_str()).unwrap_or("")
-                    }
-                },
-                "TomadorServico": {
-                    "IdentificacaoTomador": {
-                        "CpfCnpj": {
-                            "Cnpj": tomador.get("IdentificacaoTomador").and_then(|v| v.get("CpfCnpj")).and_then(|v| v.get("Cnpj")).and_then(|v| v.as_str()).unwrap_or(cnpj_padrao)
-                        },
-                        "InscricaoMunicipal": tomador.get("IdentificacaoTomador").and_then(|v| v.get("InscricaoMunicipal")).and_then(|v| v.as_str()).unwrap_or("")
-                    },
-                    "RazaoSocial": tomador.get("RazaoSocial").and_then(|v| v.as_str()).unwrap_or(""),
-                    "Endereco": {
-                        "Endereco": tomador.get("Endereco").and_then(|v| v.get("Endereco")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Numero": tomador.get("Endereco").and_then(|v| v.get("Numero")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Complemento": tomador.get("Endereco").and_then(|v| v.get("Complemento")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Bairro": tomador.get("Endereco").and_then(|v| v.get("Bairro")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "CodigoMunicipio": tomador.get("Endereco").and_then(|v| v.get("CodigoMunicipio")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Uf": tomador.get("Endereco").and_then(|v| v.get("Uf")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Cep": tomador.get("Endereco").and_then(|v| v.get("Cep")).and_then(|v| v.as_str()).unwrap_or("")
-                    },
-                    "Contato": {
-                        "Telefone": tomador.get("Contato").and_then(|v| v.get("Telefone")).and_then(|v| v.as_str()).unwrap_or(""),
-                        "Email": tomador.get("Contato").and_then(|v| v.get("Email")).and_then(|v| v.as_str()).unwrap_or("")
-                    }
-                },
-                "OrgaoGerador": {
-                    "CodigoMunicipio": inf.get("OrgaoGerador").and_then(|v| v.get("CodigoMunicipio")).and_then(|v| v.as_str()).unwrap_or(""),
-                    "Uf": inf.get("OrgaoGerador").and_then(|v| v.get("Uf")).and_then(|v| v.as_str()).unwrap_or("")
-                },
-                "IntermediarioServico": {},
-                "ContrucaoCivil": {}
+        }
+    }
+
+    if conversion.notas.is_empty() {
+        if let Some(falha) = conversion.report.failures.first() {
+            return Err(format!("Nenhuma nota convertida: {}", falha.reason));
+        }
+    }
+    Ok(conversion)
+}
+
+// Arquivo de entrada da junção, lido pelo frontend
+#[derive(Debug, serde::Deserialize)]
+struct InputFile {
+    name: String,
+    content: String,
+}
+
+// Converter cada arquivo guardando de onde veio cada nota. No modo parcial, um arquivo que
+// falha inteiro vira uma falha no relatório.
+fn convert_files(files: &[InputFile], profile: Option<&MappingProfile>, company: &CompanyProfile, partial: bool) -> Result<(Conversion, Vec<MergeEntry>), String> {
+    let mut juntas = Conversion::default();
+    let mut entries = Vec::new();
+
+    for file in files {
+        let conversion = match extract_notas(&file.content, profile, company, partial) {
+            Ok(conversion) => conversion,
+            Err(reason) if partial => {
+                juntas.report.failures.push(Failure { index: None, numero: None, reason: format!("{}: {}", file.name, reason) });
+                continue;
             }
-        });
-        
-        nfse_list.push(nfse_output);
+            Err(reason) => return Err(format!("{}: {}", file.name, reason)),
+        };
+
+        juntas.report.failures.extend(
+            conversion.report.failures.into_iter().map(|falha| Failure { reason: format!("{}: {}", file.name, falha.reason), ..falha }),
+        );
+        juntas.rejected.extend(conversion.rejected);
+        juntas.events.extend(conversion.events);
+        entries.extend(
+            conversion.notas.into_iter().zip(conversion.report.notas).map(|(nota, report)| MergeEntry {
+                file: file.name.clone(),
+                nota,
+                report,
+            }),
+        );
     }
-    
-    // Criar o objeto final seguindo EXATAMENTE o padrão do conversor.js
-    let final_obj = serde_json::json!({
-        "ListaNotaFiscal": {
-            "@_xmlns": "http://www.abrasf.org.br/nfse.xsd",
-            "@_xmlns:ns2": "http://www.w3.org/2000/09/xmldsig#",
-            "Nfse": nfse_list
+    Ok((juntas, entries))
+}
+
+// Juntar as notas de todos os arquivos num único lote, sem repetidas
+fn merge_files(files: &[InputFile], profile: Option<&MappingProfile>, company: &CompanyProfile, partial: bool) -> Result<Conversion, String> {
+    let (mut juntas, entries) = convert_files(files, profile, company, partial)?;
+    let (unicas, duplicadas) = merge::deduplicate(entries);
+    for entry in unicas {
+        juntas.notas.push(entry.nota);
+        juntas.report.notas.push(entry.report);
+    }
+    juntas.report.duplicates = duplicadas;
+
+    if juntas.notas.is_empty() {
+        if let Some(falha) = juntas.report.failures.first() {
+            return Err(format!("Nenhuma nota convertida: {}", falha.reason));
         }
-    });
+    }
+    Ok(juntas)
+}
+
+// Decidir se o conteúdo passa pela conversão de NFS-e ou só pela reformatação genérica
+fn is_nfse_content(content: &str) -> bool {
+    adn::is_package(content)
+        || read_documents(content).is_ok_and(|(documentos, _)| {
+            documentos.iter().any(|documento| {
+                roxmltree::Document::parse(documento)
+                    .is_ok_and(|doc| providers::detect(doc.root_element()).is_some())
+            })
+        })
+}
+
+// Identificar o provedor e extrair os registros de cada nota. Com um perfil de
+// mapeamento, o perfil substitui a detecção de provedor.
+fn extract_document(xml_content: &str, profile: Option<&MappingProfile>) -> Result<Vec<Record>, String> {
+    let doc = roxmltree::Document::parse(xml_content).map_err(|e| e.to_string())?;
+
+    // Parse XML para JSON-like structure
+    let json = parse_xml_to_json(xml_content)?;
+
+    let (origem, records) = match profile {
+        Some(profile) => (profile.name.as_str(), profile.records(&json)),
+        None => {
+            let adapter = providers::detect(doc.root_element())
+                .ok_or("Layout de NFS-e não reconhecido")?;
+            (adapter.name(), adapter.records(&json))
+        }
+    };
+    let mut records = records.map_err(|e| format!("{}: {}", origem, e))?;
+
+    // Os registros levam o namespace da raiz para continuarem válidos fora do documento (quarentena)
+    let namespace = doc.root_element().tag_name().namespace();
+    for record in &mut records {
+        if let Err(reason) = &mut record.inf {
+            *reason = format!("{}: {}", origem, reason);
+        }
+        if let (Some(namespace), Some(source)) = (namespace, record.source.as_object_mut()) {
+            source.entry("@_xmlns").or_insert_with(|| Value::String(namespace.to_string()));
+        }
+    }
+    Ok(records)
+}
+
+#[tauri::command]
+fn convert_and_save_xml(app: tauri::AppHandle, input_path: String, save_path: String, options: Option<ConversionOptions>) -> Result<ConversionResult, String> {
+    let options = options.unwrap_or_default();
     
-    // Converter para XML (igual ao XMLBuilder no JS)
-    let output_xml = build_xml_from_json(&final_obj);
+    // Ler o arquivo XML
+    let xml_content = fs::read_to_string(&input_path).map_err(|e| e.to_string())?;
     
-    // Salvar o arquivo (igual ao fs.writeFileSync no JS)
-    fs::write(&save_path, output_xml).map_err(|e| e.to_string())?;
+    // Perfil de mapeamento opcional, carregado do disco
+    let profile = options.profile_path.as_deref().map(mapping::load_profile).transpose()?;
     
-    Ok(save_path)
+    let company = company::load(&app)?.selected_profile();
+    
+    let mut conversion = extract_notas(&xml_content, profile.as_ref(), &company, options.partial)?;
+    conversion.finish(&options)?;
+    
+    // Gerar o formato escolhido (ListaNotaFiscal por padrão, igual ao XMLBuilder no JS)
+    let saidas = render_output(std::mem::take(&mut conversion.notas), &options)?;
+    
+    // Salvar o arquivo (igual ao fs.writeFileSync no JS)
+    save_conversion(Path::new(&save_path), saidas, conversion, &options)
 }
 
 #[tauri::command]
@@ -312,37 +519,339 @@ fn convert_xml_content(xml_content: String, _original_file_name: String) -> Resu
     Ok(converted_xml)
 }
 
+// Árvore intermediária do XML (a mesma que o conversor usa) com as convenções de JSON
+// configuradas. O XML é lido como está, sem a detecção de NFS-e; só um envelope SOAP é
+// desembrulhado, e um Body com vários elementos vira uma lista.
+fn xml_tree_json(content: &str, settings: &JsonSettings) -> Result<String, String> {
+    settings.validate()?;
+    let mut arvores = soap::unwrap_envelope(content)?
+        .iter()
+        .map(|documento| parse_xml_to_json(documento))
+        .collect::<Result<Vec<_>, String>>()?;
+    let arvore = if arvores.len() == 1 { arvores.remove(0) } else { Value::Array(arvores) };
+    settings.to_string(&settings.apply(arvore))
+}
+
 #[tauri::command]
-fn convert_and_save_xml_with_dialog(xml_content: String, original_file_name: String) -> Result<String, String> {
-    // Parse do XML para JSON
-    let json_value = parse_xml_to_json(&xml_content)?;
-    
-    // Conversão de volta para XML
-    let converted_xml = build_xml_from_json(&json_value);
+fn save_xml_tree_json(input_path: String, save_path: String, settings: Option<JsonSettings>) -> Result<String, String> {
+    let content = fs::read_to_string(&input_path).map_err(|e| e.to_string())?;
+    let json = xml_tree_json(&content, &settings.unwrap_or_default())?;
+    fs::write(&save_path, json).map_err(|e| e.to_string())?;
+    Ok(save_path)
+}
+
+#[tauri::command]
+fn save_xml_tree_json_with_dialog(xml_content: String, original_file_name: String, settings: Option<JsonSettings>) -> Result<String, String> {
+    let json = xml_tree_json(&xml_content, &settings.unwrap_or_default())?;
+    let stem = Path::new(&original_file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "documento".to_string());
+
+    let file_path = FileDialog::new()
+        .set_file_name(format!("{}.json", stem))
+        .add_filter("JSON Files", &["json"])
+        .save_file();
+
+    match file_path {
+        Some(path) => {
+            fs::write(&path, json).map_err(|e| e.to_string())?;
+            Ok(path.to_string_lossy().to_string())
+        }
+        None => Err("Operação cancelada pelo usuário".to_string())
+    }
+}
+
+// XML qualquer (fora de NFS-e) achatado em CSV, uma linha por registro. Lido como está, sem a
+// detecção de NFS-e; só um envelope SOAP é desembrulhado.
+fn flatten_xml(content: &str, options: &FlattenOptions) -> Result<(String, usize, Vec<String>), String> {
+    let arvores = soap::unwrap_envelope(content)?
+        .iter()
+        .map(|documento| parse_xml_to_json(documento))
+        .collect::<Result<Vec<_>, String>>()?;
+    flatten::to_csv(&arvores, options)
+}
+
+#[tauri::command]
+fn flatten_xml_to_csv(input_path: String, save_path: String, options: FlattenOptions) -> Result<FlattenResult, String> {
+    let content = fs::read_to_string(&input_path).map_err(|e| e.to_string())?;
+    let (csv, records, columns) = flatten_xml(&content, &options)?;
+    fs::write(&save_path, csv).map_err(|e| e.to_string())?;
+    Ok(FlattenResult { saved_path: save_path, records, columns })
+}
+
+#[tauri::command]
+fn flatten_xml_to_csv_with_dialog(xml_content: String, original_file_name: String, options: FlattenOptions) -> Result<FlattenResult, String> {
+    // Gerar antes do diálogo, para os erros de caminho aparecerem sem perguntar onde salvar
+    let (csv, records, columns) = flatten_xml(&xml_content, &options)?;
+    let stem = Path::new(&original_file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "registros".to_string());
+
+    let file_path = FileDialog::new()
+        .set_file_name(format!("{}.csv", stem))
+        .add_filter("CSV Files", &["csv"])
+        .save_file();
+
+    match file_path {
+        Some(path) => {
+            fs::write(&path, csv).map_err(|e| e.to_string())?;
+            Ok(FlattenResult { saved_path: path.to_string_lossy().to_string(), records, columns })
+        }
+        None => Err("Operação cancelada pelo usuário".to_string())
+    }
+}
+
+// JSON (correções ou saída de outra ferramenta) para XML, com um único elemento raiz
+#[tauri::command]
+fn convert_json_to_xml(input_path: String, save_path: String, options: Option<JsonImportOptions>) -> Result<String, String> {
+    let content = fs::read_to_string(&input_path).map_err(|e| e.to_string())?;
+    let xml = json_import::to_xml(&content, &options.unwrap_or_default())?;
+    fs::write(&save_path, xml).map_err(|e| e.to_string())?;
+    Ok(save_path)
+}
+
+#[tauri::command]
+fn convert_json_to_xml_with_dialog(json_content: String, original_file_name: String, options: Option<JsonImportOptions>) -> Result<String, String> {
+    let xml = json_import::to_xml(&json_content, &options.unwrap_or_default())?;
+    let stem = Path::new(&original_file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "documento".to_string());
+
+    let file_path = FileDialog::new()
+        .set_file_name(format!("{}.xml", stem))
+        .add_filter("XML Files", &["xml"])
+        .save_file();
+
+    match file_path {
+        Some(path) => {
+            fs::write(&path, xml).map_err(|e| e.to_string())?;
+            Ok(path.to_string_lossy().to_string())
+        }
+        None => Err("Operação cancelada pelo usuário".to_string())
+    }
+}
+
+#[tauri::command]
+fn convert_and_save_xml_with_dialog(app: tauri::AppHandle, xml_content: String, original_file_name: String, options: Option<ConversionOptions>) -> Result<ConversionResult, String> {
+    let options = options.unwrap_or_default();
+    let output_format = options.output_format;
+    let profile = options.profile_path.as_deref().map(mapping::load_profile).transpose()?;
+    let (saidas, conversion) = if profile.is_some() || is_nfse_content(&xml_content) {
+        // NFS-e (inclusive pacotes do ADN): converter todas as notas para o formato escolhido
+        let company = company::load(&app)?.selected_profile();
+        let mut conversion = extract_notas(&xml_content, profile.as_ref(), &company, options.partial)?;
+        conversion.finish(&options)?;
+        (render_output(std::mem::take(&mut conversion.notas), &options)?, conversion)
+    } else {
+        if output_format.is_nota_only() {
+            return Err(format!("O formato {} só pode ser gerado a partir de NFS-e; para outros XML, use o achatamento em CSV", output_format.extension().to_uppercase()));
+        }
+        // Parse do XML para JSON e conversão de volta para XML
+        let documento = writers::OutputDocument {
+            suffix: None,
+            content: build_xml_from_json(&parse_xml_to_json(&xml_content)?).into_bytes(),
+        };
+        (vec![Output { group: None, documentos: vec![documento] }], Conversion::default())
+    };
     
-    // Criar nome padrão para o arquivo convertido
-    let default_name = original_file_name.replace(".xml", "-convertido.xml");
+    // Criar nome padrão para o arquivo convertido (a entrada pode ser .xml ou .json)
+    let stem = Path::new(&original_file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "notas".to_string());
+    let default_name = format!("{}-convertido.{}", stem, output_format.extension());
     
     // Abrir diálogo para salvar
     let file_path = FileDialog::new()
         .set_file_name(&default_name)
-        .add_filter("XML Files", &["xml"])
+        .add_filter(output_format.filter_name(), &[output_format.extension()])
         .save_file();
     
     match file_path {
-        Some(path) => {
-            fs::write(&path, &converted_xml).map_err(|e| e.to_string())?;
-            Ok(path.to_string_lossy().to_string())
-        }
+        Some(path) => save_conversion(&path, saidas, conversion, &options),
         None => Err("Operação cancelada pelo usuário".to_string())
     }
 }
 
+#[tauri::command]
+fn merge_and_save_xml(app: tauri::AppHandle, input_paths: Vec<String>, save_path: String, options: Option<ConversionOptions>) -> Result<ConversionResult, String> {
+    let options = options.unwrap_or_default();
+    let files = input_paths
+        .iter()
+        .map(|path| {
+            let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            let name = Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+            Ok(InputFile { name, content })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    
+    let profile = options.profile_path.as_deref().map(mapping::load_profile).transpose()?;
+    let company = company::load(&app)?.selected_profile();
+    let mut conversion = merge_files(&files, profile.as_ref(), &company, options.partial)?;
+    conversion.finish(&options)?;
+    
+    let saidas = render_output(std::mem::take(&mut conversion.notas), &options)?;
+    save_conversion(Path::new(&save_path), saidas, conversion, &options)
+}
+
+#[tauri::command]
+fn merge_and_save_xml_with_dialog(app: tauri::AppHandle, files: Vec<InputFile>, options: Option<ConversionOptions>) -> Result<ConversionResult, String> {
+    let options = options.unwrap_or_default();
+    let output_format = options.output_format;
+    let profile = options.profile_path.as_deref().map(mapping::load_profile).transpose()?;
+    let company = company::load(&app)?.selected_profile();
+    let mut conversion = merge_files(&files, profile.as_ref(), &company, options.partial)?;
+    conversion.finish(&options)?;
+    let saidas = render_output(std::mem::take(&mut conversion.notas), &options)?;
+    
+    let file_path = FileDialog::new()
+        .set_file_name(format!("notas-juntas.{}", output_format.extension()))
+        .add_filter(output_format.filter_name(), &[output_format.extension()])
+        .save_file();
+    
+    match file_path {
+        Some(path) => save_conversion(&path, saidas, conversion, &options),
+        None => Err("Operação cancelada pelo usuário".to_string())
+    }
+}
+
+// Totais das notas sem gravar a saída: mesmas etapas da conversão (filtro e canceladas inclusive)
+#[tauri::command]
+fn summarize_files(app: tauri::AppHandle, files: Vec<InputFile>, options: Option<ConversionOptions>) -> Result<Summary, String> {
+    let options = options.unwrap_or_default();
+    let profile = options.profile_path.as_deref().map(mapping::load_profile).transpose()?;
+    let company = company::load(&app)?.selected_profile();
+    let mut conversion = merge_files(&files, profile.as_ref(), &company, options.partial)?;
+    conversion.finish(&options)?;
+    Ok(conversion.summary)
+}
+
+// Lacunas e repetições na numeração de cada prestador, com as notas como vieram dos arquivos
+// (sem remover repetidas nem aplicar filtro: canceladas também ocupam número)
+#[tauri::command]
+fn analyze_numbering(app: tauri::AppHandle, files: Vec<InputFile>, options: Option<ConversionOptions>) -> Result<NumberingReport, String> {
+    let options = options.unwrap_or_default();
+    let profile = options.profile_path.as_deref().map(mapping::load_profile).transpose()?;
+    let company = company::load(&app)?.selected_profile();
+    let (conversion, entries) = convert_files(&files, profile.as_ref(), &company, options.partial)?;
+    if entries.is_empty() {
+        if let Some(falha) = conversion.report.failures.first() {
+            return Err(format!("Nenhuma nota convertida: {}", falha.reason));
+        }
+    }
+    Ok(NumberingReport { prestadores: numbering::analyze(&entries), failures: conversion.report.failures })
+}
+
+// Documentos de saída; com a divisão ligada, um conjunto por grupo de notas
+struct Output {
+    group: Option<SplitGroup>,
+    documentos: Vec<writers::OutputDocument>,
+}
+
+// Gerar o formato escolhido, antes de perguntar onde salvar (erros aparecem sem o diálogo)
+fn render_output(notas: Vec<Value>, options: &ConversionOptions) -> Result<Vec<Output>, String> {
+    if options.output_format == OutputFormat::Csv {
+        options.csv.validate()?;
+    }
+    options.json.validate()?;
+    let Some(key) = options.split else {
+        return Ok(vec![Output { group: None, documentos: writers::render(options.output_format, notas, &options.csv, &options.json)? }]);
+    };
+    split::validate_template(options.split_template.as_deref().unwrap_or(split::DEFAULT_TEMPLATE), key)?;
+    split::partition(notas, key)
+        .into_iter()
+        .map(|(group, notas)| Ok(Output { group: Some(group), documentos: writers::render(options.output_format, notas, &options.csv, &options.json)? }))
+        .collect()
+}
+
+// Gravar a saída e, conforme as opções, o relatório e a quarentena ao lado dela
+fn save_conversion(save_path: &Path, outputs: Vec<Output>, conversion: Conversion, options: &ConversionOptions) -> Result<ConversionResult, String> {
+    let template = options.split_template.as_deref().unwrap_or(split::DEFAULT_TEMPLATE);
+    let stem = save_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_else(|| "notas".to_string());
+    let extension = save_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_else(|| options.output_format.extension().to_string());
+    
+    let mut paths = Vec::new();
+    for output in outputs {
+        let path = match &output.group {
+            Some(group) => save_path.with_file_name(format!("{}.{}", split::file_name(template, &stem, group), extension)),
+            None => save_path.to_path_buf(),
+        };
+        paths.extend(writers::save(&path, output.documentos)?);
+    }
+    let saved_path = match paths.as_slice() {
+        [unico] => unico.to_string_lossy().to_string(),
+        _ => format!("{} arquivos em {}", paths.len(), save_path.parent().unwrap_or(save_path).to_string_lossy()),
+    };
+    
+    let report_path = options
+        .report_format
+        .map(|format| conversion.report.save(save_path, format))
+        .transpose()?;
+    let quarantine_path = if options.quarantine {
+        writers::save_quarantine(save_path, &conversion.rejected)?
+    } else {
+        None
+    };
+    
+    // Canceladas no mesmo formato, em <nome>-canceladas
+    let cancelled_path = if conversion.cancelled.is_empty() {
+        None
+    } else {
+        let path = writers::suffixed_path(save_path, "canceladas");
+        let documentos = writers::render(options.output_format, conversion.cancelled, &options.csv, &options.json)?;
+        writers::save(&path, documentos)?;
+        Some(path.to_string_lossy().to_string())
+    };
+    
+    let summary_path = options
+        .summary_format
+        .map(|format| conversion.summary.save(save_path, format))
+        .transpose()?;
+    
+    Ok(ConversionResult {
+        saved_path,
+        report_path: report_path.map(|path| path.to_string_lossy().to_string()),
+        quarantine_path: quarantine_path.map(|path| path.to_string_lossy().to_string()),
+        cancelled_path,
+        summary_path: summary_path.map(|path| path.to_string_lossy().to_string()),
+        report: conversion.report,
+        summary: conversion.summary,
+    })
+}
+
+#[tauri::command]
+fn list_company_profiles(app: tauri::AppHandle) -> Result<CompanyProfiles, String> {
+    company::load(&app)
+}
+
+#[tauri::command]
+fn select_company_profile(app: tauri::AppHandle, name: String) -> Result<CompanyProfiles, String> {
+    let mut profiles = company::load(&app)?;
+    profiles.select(&name)?;
+    company::save(&app, &profiles)?;
+    Ok(profiles)
+}
+
+// Criar ou editar um perfil de empresa (identificado pelo nome)
+#[tauri::command]
+fn save_company_profile(app: tauri::AppHandle, profile: CompanyProfile) -> Result<CompanyProfiles, String> {
+    let mut profiles = company::load(&app)?;
+    profiles.upsert(profile)?;
+    company::save(&app, &profiles)?;
+    Ok(profiles)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet, convert_and_save_xml, convert_xml_content, convert_and_save_xml_with_dialog])
+        .invoke_handler(tauri::generate_handler![greet, convert_and_save_xml, convert_xml_content, save_xml_tree_json, save_xml_tree_json_with_dialog, convert_json_to_xml, convert_json_to_xml_with_dialog, flatten_xml_to_csv, flatten_xml_to_csv_with_dialog, convert_and_save_xml_with_dialog, merge_and_save_xml, merge_and_save_xml_with_dialog, summarize_files, analyze_numbering, list_company_profiles, select_company_profile, save_company_profile])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,220 @@
+// Perfis de mapeamento declarativos (JSON): caminhos de origem, caminho de destino,
+// transformação e valor padrão de cada campo. O perfil embutido reproduz a conversão
+// ConsultarNfseResposta -> ListaNotaFiscal; perfis de clientes são carregados do disco.
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::dates;
+use crate::nota::{as_list, get_path, set_path, text_at};
+use crate::providers::Record;
+use crate::report::NotaReport;
+
+const DEFAULT_PROFILE: &str = include_str!("../profiles/consultar_nfse_abrasf.json");
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct MappingProfile {
+    pub(crate) name: String,
+    // Caminho da lista de registros no documento (ex.: ConsultarNfseResposta/ListaNfse/CompNfse)
+    #[serde(default)]
+    pub(crate) records: Option<String>,
+    // Caminho dos dados da nota dentro de cada registro (ex.: Nfse/InfNfse)
+    #[serde(default)]
+    pub(crate) record_root: Option<String>,
+    pub(crate) fields: Vec<FieldMapping>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct FieldMapping {
+    pub(crate) target: String,
+    #[serde(default)]
+    pub(crate) source: Sources,
+    #[serde(default)]
+    pub(crate) transform: Transform,
+    // Usado quando nenhuma origem tem valor; null mantém o campo vazio
+    #[serde(default)]
+    pub(crate) default: Value,
+    // Sem valor na origem, o campo fica de fora da nota (em vez de vazio)
+    #[serde(default)]
+    pub(crate) optional: bool,
+}
+
+// Uma origem ("Numero") ou várias, tentadas em ordem (["@_Id", "Id"])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Sources {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Sources {
+    fn default() -> Self {
+        Sources::Many(Vec::new())
+    }
+}
+
+impl Sources {
+    fn paths(&self) -> &[String] {
+        match self {
+            Sources::One(path) => std::slice::from_ref(path),
+            Sources::Many(paths) => paths,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Transform {
+    #[default]
+    Text,
+    Number,
+    Integer,
+    // Completar com zeros à esquerda até o tamanho informado
+    PadLeft(usize),
+    // "202505Z" -> "2025-05-01T00:00:00" (variações em dates.rs); não reconhecidas passam intactas
+    Competencia,
+}
+
+impl Transform {
+    // None quando o texto de origem não serve para o tipo (ex.: número inválido)
+    fn apply(self, text: &str) -> Option<Value> {
+        match self {
+            Transform::Text => Some(Value::String(text.to_string())),
+            Transform::Number => text.trim().parse::<f64>().ok().map(Value::from),
+            Transform::Integer => text.trim().parse::<f64>().ok().map(|v| Value::from(v as i64)),
+            Transform::PadLeft(width) => Some(Value::String(format!("{:0>width$}", text.trim(), width = width))),
+            Transform::Competencia => Some(Value::String(match dates::parse(text) {
+                Some(data) => data.local.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                None => text.to_string(),
+            })),
+        }
+    }
+}
+
+// Houve mudança de conteúdo, e não só de tipo ("1000.00" -> 1000.0 não conta)
+fn is_coercion(text: &str, value: &Value) -> bool {
+    match value {
+        Value::String(s) => s != text,
+        Value::Number(n) => n.as_f64() != text.trim().parse::<f64>().ok(),
+        _ => false,
+    }
+}
+
+// Caminhos das folhas com valor no registro de origem ("A/B/C" -> valor)
+fn leaf_values(value: &Value, prefix: &str, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                // Declarações de namespace não são dados da nota
+                if key.starts_with("@_xmlns") {
+                    continue;
+                }
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}/{}", prefix, key) };
+                leaf_values(child, &path, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                leaf_values(item, prefix, out);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) if s.trim().is_empty() => {}
+        leaf => out.push((prefix.to_string(), leaf.clone())),
+    }
+}
+
+impl MappingProfile {
+    // Aplicar o perfil a um registro (o InfNfse de origem), gerando a nota do modelo interno
+    // e anotando no relatório os valores padrão, descartes e conversões
+    pub(crate) fn map_record(&self, record: &Value, report: &mut NotaReport) -> Value {
+        let mut nota = Map::new();
+        for field in &self.fields {
+            let mut value = None;
+            for path in field.source.paths() {
+                let Some(text) = text_at(record, path) else {
+                    continue;
+                };
+                match field.transform.apply(&text) {
+                    Some(convertido) => {
+                        if is_coercion(&text, &convertido) {
+                            report.coerced(&field.target, &text, &convertido);
+                        }
+                        value = Some(convertido);
+                        break;
+                    }
+                    // Valor inválido para o tipo do campo: descartado, segue para a próxima origem
+                    None => report.dropped(path, &Value::String(text)),
+                }
+            }
+
+            let value = match value {
+                Some(value) => value,
+                None if field.optional => continue,
+                None => {
+                    report.defaulted(&field.target, &field.default);
+                    field.default.clone()
+                }
+            };
+            set_path(&mut nota, &field.target, value);
+        }
+
+        // Dados da origem que nenhum campo do perfil aproveita
+        let mut folhas = Vec::new();
+        leaf_values(record, "", &mut folhas);
+        for (path, valor) in folhas {
+            let mapeado = self.fields.iter().any(|field| {
+                field.source.paths().iter().any(|source| {
+                    path == *source || path.strip_prefix(source.as_str()).is_some_and(|resto| resto.starts_with('/'))
+                })
+            });
+            if !mapeado {
+                report.dropped(&path, &valor);
+            }
+        }
+
+        report.numero = nota.get("Numero").and_then(Value::as_str).unwrap_or_default().to_string();
+        Value::Object(nota)
+    }
+
+    // Localizar os registros (um por nota) num documento já convertido por parse_xml_to_json
+    pub(crate) fn records(&self, tree: &Value) -> Result<Vec<Record>, String> {
+        let records_path = self
+            .records
+            .as_deref()
+            .ok_or_else(|| format!("Perfil \"{}\" não define o caminho dos registros (records)", self.name))?;
+        let registros = get_path(tree, records_path)
+            .ok_or("Não foram encontradas notas fiscais no XML")?;
+        let element = records_path.rsplit('/').next().unwrap_or(records_path);
+
+        Ok(as_list(registros)
+            .iter()
+            .map(|registro| {
+                let record = match &self.record_root {
+                    Some(path) => get_path(registro, path)
+                        .cloned()
+                        .ok_or_else(|| format!("Estrutura {} não encontrada", path)),
+                    None => Ok(registro.clone()),
+                };
+                Record::new(element, registro, record)
+            })
+            .collect())
+    }
+}
+
+pub(crate) fn default_profile() -> &'static MappingProfile {
+    static PROFILE: OnceLock<MappingProfile> = OnceLock::new();
+    PROFILE.get_or_init(|| serde_json::from_str(DEFAULT_PROFILE).expect("perfil de mapeamento embutido inválido"))
+}
+
+pub(crate) fn load_profile(path: &str) -> Result<MappingProfile, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let profile: MappingProfile = serde_json::from_str(&content)
+        .map_err(|e| format!("Perfil de mapeamento inválido ({}): {}", path, e))?;
+
+    if profile.records.is_none() {
+        return Err(format!("Perfil \"{}\" não define o caminho dos registros (records)", profile.name));
+    }
+    Ok(profile)
+}
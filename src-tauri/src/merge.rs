@@ -0,0 +1,101 @@
+// Junção de vários arquivos num único lote: notas repetidas entre as páginas baixadas são
+// identificadas por (CNPJ do prestador, Numero, CodigoVerificacao) e aparecem uma vez só.
+// Versões diferentes da mesma nota ficam registradas como conflito no relatório.
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::nota::str_or;
+use crate::report::NotaReport;
+
+// Nota convertida e o arquivo de onde veio
+pub(crate) struct MergeEntry {
+    pub(crate) file: String,
+    pub(crate) nota: Value,
+    pub(crate) report: NotaReport,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Duplicate {
+    pub(crate) numero: String,
+    pub(crate) prestador: String,
+    pub(crate) codigo_verificacao: String,
+    // Arquivo da versão mantida e da versão descartada
+    pub(crate) kept_file: String,
+    pub(crate) dropped_file: String,
+    // Campos em que as versões divergem; vazio quando são idênticas
+    pub(crate) conflicts: Vec<String>,
+}
+
+type Chave = (String, String, String);
+
+fn key(nota: &Value) -> Chave {
+    (
+        str_or(nota, "PrestadorServico/IdentificacaoPrestador/Cnpj", ""),
+        str_or(nota, "Numero", "").trim_start_matches('0').to_string(),
+        str_or(nota, "CodigoVerificacao", ""),
+    )
+}
+
+// Caminhos dos valores que diferem entre duas versões
+fn differing_fields(a: &Value, b: &Value, prefixo: &str, campos: &mut Vec<String>) {
+    match (a, b) {
+        (Value::Object(x), Value::Object(y)) => {
+            let mut chaves: Vec<&String> = x.keys().chain(y.keys().filter(|k| !x.contains_key(*k))).collect();
+            chaves.sort();
+            for chave in chaves {
+                let caminho = if prefixo.is_empty() { chave.clone() } else { format!("{}/{}", prefixo, chave) };
+                differing_fields(x.get(chave).unwrap_or(&Value::Null), y.get(chave).unwrap_or(&Value::Null), &caminho, campos);
+            }
+        }
+        _ if a != b => campos.push(prefixo.to_string()),
+        _ => {}
+    }
+}
+
+// Ordem estável: prestador, número (numérico) e código de verificação
+fn sort_key(chave: &Chave) -> (String, usize, String, String) {
+    let (prestador, numero, codigo) = chave;
+    (prestador.clone(), numero.len(), numero.clone(), codigo.clone())
+}
+
+// Remover repetidas e ordenar. Entre versões divergentes fica a primeira, salvo quando só a
+// outra traz o cancelamento ou a substituição (baixada depois do evento).
+pub(crate) fn deduplicate(entries: Vec<MergeEntry>) -> (Vec<MergeEntry>, Vec<Duplicate>) {
+    let mut unicas: Vec<MergeEntry> = Vec::new();
+    let mut posicoes: HashMap<Chave, usize> = HashMap::new();
+    let mut duplicadas = Vec::new();
+
+    for entry in entries {
+        let chave = key(&entry.nota);
+        let Some(&posicao) = posicoes.get(&chave) else {
+            posicoes.insert(chave, unicas.len());
+            unicas.push(entry);
+            continue;
+        };
+
+        let existente = &mut unicas[posicao];
+        let mut conflicts = Vec::new();
+        differing_fields(&existente.nota, &entry.nota, "", &mut conflicts);
+        let substituir = existente.report.status.is_active() && !entry.report.status.is_active();
+        let (mantida, descartada) = if substituir {
+            let anterior = std::mem::replace(existente, entry);
+            (existente.file.clone(), anterior.file)
+        } else {
+            (existente.file.clone(), entry.file)
+        };
+
+        let (prestador, numero, codigo_verificacao) = chave;
+        duplicadas.push(Duplicate {
+            numero: str_or(&unicas[posicao].nota, "Numero", &numero),
+            prestador,
+            codigo_verificacao,
+            kept_file: mantida,
+            dropped_file: descartada,
+            conflicts,
+        });
+    }
+
+    unicas.sort_by_cached_key(|entry| sort_key(&key(&entry.nota)));
+    (unicas, duplicadas)
+}
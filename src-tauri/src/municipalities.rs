@@ -0,0 +1,114 @@
+// Códigos de município do IBGE (7 dígitos: UF, município e dígito verificador) e a UF de cada
+// endereço da nota. A conferência usa o dígito e o código da UF; os nomes vêm da tabela
+// embutida (tables/municipios.json), que pode ser substituída pela exportação completa do IBGE.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::nota::{set_path, text_at};
+use crate::report::NotaReport;
+
+// (caminho do código do município, caminho da UF); o Servico não traz UF
+const LOCAIS: &[(&str, Option<&str>)] = &[
+    ("Servico/CodigoMunicipio", None),
+    ("PrestadorServico/Endereco/CodigoMunicipio", Some("PrestadorServico/Endereco/Uf")),
+    ("TomadorServico/Endereco/CodigoMunicipio", Some("TomadorServico/Endereco/Uf")),
+    ("OrgaoGerador/CodigoMunicipio", Some("OrgaoGerador/Uf")),
+];
+
+// Municípios criados com dígito verificador fora da regra, mantidos assim pelo IBGE
+const DV_EXCECOES: &[&str] = &["2201919", "2201988", "2202251", "2611533", "3117836", "3152131", "4305871", "5203939", "5203962"];
+
+#[derive(Debug, Deserialize)]
+struct Tabela {
+    ufs: HashMap<String, String>,
+    municipios: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct MunicipalitySettings {
+    // Incluir o nome do município de cada código no relatório
+    pub(crate) names_in_report: bool,
+}
+
+fn tabela() -> &'static Tabela {
+    static TABELA: OnceLock<Tabela> = OnceLock::new();
+    TABELA.get_or_init(|| {
+        serde_json::from_str(include_str!("../tables/municipios.json")).expect("tabela de municípios embutida inválida")
+    })
+}
+
+// Pesos 1 e 2 alternados nos 6 primeiros dígitos, somando os algarismos de cada produto
+fn check_digit(base: &[u8]) -> u8 {
+    let soma: u32 = base
+        .iter()
+        .zip([1, 2].iter().cycle())
+        .map(|(c, peso)| {
+            let produto = u32::from(c - b'0') * peso;
+            produto / 10 + produto % 10
+        })
+        .sum();
+    ((10 - soma % 10) % 10) as u8
+}
+
+pub(crate) fn uf_of(codigo: &str) -> Option<&'static str> {
+    tabela().ufs.get(codigo.get(..2)?).map(String::as_str)
+}
+
+pub(crate) fn name_of(codigo: &str) -> Option<&'static str> {
+    tabela().municipios.get(codigo).map(String::as_str)
+}
+
+pub(crate) fn is_valid_code(codigo: &str) -> bool {
+    let bytes = codigo.as_bytes();
+    if bytes.len() != 7 || !bytes.iter().all(u8::is_ascii_digit) || uf_of(codigo).is_none() {
+        return false;
+    }
+    bytes[6] - b'0' == check_digit(&bytes[..6]) || DV_EXCECOES.contains(&codigo)
+}
+
+impl MunicipalitySettings {
+    // Conferir os códigos, a UF de cada endereço e preencher a UF ausente pelo código
+    pub(crate) fn normalize_nota(&self, nota: &mut Value, report: &mut NotaReport) {
+        for (codigo_path, uf_path) in LOCAIS {
+            let Some(codigo) = text_at(nota, codigo_path).map(|s| s.trim().to_string()).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+            if !is_valid_code(&codigo) {
+                report.warn(format!("{}: código de município inválido: {}", codigo_path, codigo));
+                continue;
+            }
+            let Some(uf_codigo) = uf_of(&codigo) else {
+                continue;
+            };
+
+            if self.names_in_report {
+                let nome = name_of(&codigo).map(|nome| format!("{}/{}", nome, uf_codigo));
+                report.municipality(codigo_path, &codigo, nome);
+            }
+
+            let Some(uf_path) = uf_path else {
+                continue;
+            };
+            let original = text_at(nota, uf_path).unwrap_or_default();
+            let uf = original.trim().to_uppercase();
+            if uf.is_empty() {
+                let valor = Value::String(uf_codigo.to_string());
+                report.defaulted(uf_path, &valor);
+                if let Some(campos) = nota.as_object_mut() {
+                    set_path(campos, uf_path, valor);
+                }
+            } else if uf != uf_codigo {
+                report.warn(format!("{}: UF {} não corresponde ao município {} ({})", uf_path, original, codigo, uf_codigo));
+            } else if uf != original {
+                let valor = Value::String(uf);
+                report.coerced(uf_path, &original, &valor);
+                if let Some(campos) = nota.as_object_mut() {
+                    set_path(campos, uf_path, valor);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Modelo interno de nota: o objeto InfNfse já normalizado no layout da ListaNotaFiscal.
+// Toda nota convertida (adaptador de provedor + perfil de mapeamento) segue este formato.
+use serde_json::{Map, Value};
+
+pub(crate) const ABRASF_NAMESPACE: &str = "http://www.abrasf.org.br/nfse.xsd";
+pub(crate) const XMLDSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+// Navegar por um caminho no formato "A/B/C"
+pub(crate) fn get_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| current.get(segment))
+}
+
+// Gravar o valor no caminho de destino, criando os objetos intermediários que faltarem
+pub(crate) fn set_path(target: &mut Map<String, Value>, path: &str, value: Value) {
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let Some(last) = segments.pop() else {
+        return;
+    };
+
+    let mut current = target;
+    for segment in segments {
+        let entry = current
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !entry.is_object() {
+            *entry = Value::Object(Map::new());
+        }
+        current = entry.as_object_mut().unwrap();
+    }
+    current.insert(last.to_string(), value);
+}
+
+// Texto de um campo, aceitando tanto string quanto número (o parser gera sempre strings)
+pub(crate) fn text_at(value: &Value, path: &str) -> Option<String> {
+    match get_path(value, path)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) fn str_or(value: &Value, path: &str, default: &str) -> String {
+    text_at(value, path).unwrap_or_else(|| default.to_string())
+}
+
+pub(crate) fn number_at(value: &Value, path: &str) -> Option<f64> {
+    match get_path(value, path)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// Converter para array se for um único item (igual ao JS: Array.isArray(x) ? x : [x])
+pub(crate) fn as_list(value: &Value) -> Vec<Value> {
+    match value {
+        Value::Array(arr) => arr.clone(),
+        single => vec![single.clone()],
+    }
+}
+
+// Criar o objeto final seguindo EXATAMENTE o padrão do conversor.js
+pub(crate) fn lista_nota_fiscal(notas: Vec<Value>) -> Value {
+    let nfse_list: Vec<Value> = notas
+        .into_iter()
+        .map(|nota| serde_json::json!({ "InfNfse": nota }))
+        .collect();
+
+    serde_json::json!({
+        "ListaNotaFiscal": {
+            "@_xmlns": ABRASF_NAMESPACE,
+            "@_xmlns:ns2": XMLDSIG_NAMESPACE,
+            "Nfse": nfse_list
+        }
+    })
+}
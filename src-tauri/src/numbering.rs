@@ -0,0 +1,156 @@
+// Auditoria da numeração por prestador: faixas de Numero ausentes, notas repetidas, números
+// reaproveitados com outro CodigoVerificacao e emissões fora da ordem da numeração. Usa as
+// notas como vieram de cada arquivo, antes da remoção de repetidas da junção.
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::dates;
+use crate::merge::MergeEntry;
+use crate::nota::{str_or, text_at};
+use crate::report::Failure;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct NumberRange {
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct RepeatedNumber {
+    pub(crate) numero: String,
+    // Arquivo de cada ocorrência (repetidas) ou código de verificação de cada versão (reaproveitados)
+    pub(crate) occurrences: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct OutOfOrder {
+    pub(crate) numero: String,
+    pub(crate) data_emissao: String,
+    pub(crate) previous_numero: String,
+    pub(crate) previous_data_emissao: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct PrestadorSequence {
+    pub(crate) prestador: String,
+    pub(crate) razao_social: Option<String>,
+    pub(crate) first: String,
+    pub(crate) last: String,
+    pub(crate) count: usize,
+    pub(crate) missing: Vec<NumberRange>,
+    pub(crate) duplicates: Vec<RepeatedNumber>,
+    pub(crate) reused: Vec<RepeatedNumber>,
+    pub(crate) out_of_order: Vec<OutOfOrder>,
+    // Numero que não é numérico e fica fora da análise de sequência
+    pub(crate) invalid: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct NumberingReport {
+    pub(crate) prestadores: Vec<PrestadorSequence>,
+    // Arquivos ou notas que não puderam ser lidos (modo parcial)
+    pub(crate) failures: Vec<Failure>,
+}
+
+// Ocorrência de um número: arquivo, código de verificação e data de emissão
+struct Ocorrencia {
+    file: String,
+    codigo: String,
+    emissao: Option<String>,
+}
+
+// Mesmo preenchimento com zeros das notas do grupo (8 posições no ListaNotaFiscal)
+fn pad(numero: u64, largura: usize) -> String {
+    format!("{:0>width$}", numero, width = largura)
+}
+
+fn analyze_prestador(prestador: String, entries: &[&MergeEntry]) -> PrestadorSequence {
+    let mut sequencia = PrestadorSequence {
+        prestador,
+        razao_social: entries
+            .iter()
+            .find_map(|entry| text_at(&entry.nota, "PrestadorServico/RazaoSocial").filter(|nome| !nome.is_empty())),
+        ..PrestadorSequence::default()
+    };
+    let largura = entries.iter().map(|entry| str_or(&entry.nota, "Numero", "").len()).max().unwrap_or(8);
+
+    let mut numeros: BTreeMap<u64, Vec<Ocorrencia>> = BTreeMap::new();
+    for entry in entries {
+        let texto = str_or(&entry.nota, "Numero", "");
+        match texto.trim().parse::<u64>() {
+            Ok(numero) => numeros.entry(numero).or_default().push(Ocorrencia {
+                file: entry.file.clone(),
+                codigo: str_or(&entry.nota, "CodigoVerificacao", ""),
+                emissao: text_at(&entry.nota, "DataEmissao"),
+            }),
+            Err(_) => sequencia.invalid.push(texto),
+        }
+    }
+
+    sequencia.count = numeros.len();
+    sequencia.first = numeros.keys().next().map(|n| pad(*n, largura)).unwrap_or_default();
+    sequencia.last = numeros.keys().next_back().map(|n| pad(*n, largura)).unwrap_or_default();
+
+    // Último número visto e último com data de emissão legível
+    let mut anterior: Option<u64> = None;
+    let mut emitida_antes: Option<(u64, String, chrono::NaiveDateTime)> = None;
+    for (&numero, ocorrencias) in &numeros {
+        if let Some(numero_anterior) = anterior.filter(|n| numero > n + 1) {
+            sequencia.missing.push(NumberRange {
+                from: pad(numero_anterior + 1, largura),
+                to: pad(numero - 1, largura),
+                count: numero - numero_anterior - 1,
+            });
+        }
+        anterior = Some(numero);
+
+        let mut codigos: Vec<String> = ocorrencias.iter().map(|o| o.codigo.clone()).collect();
+        codigos.sort();
+        codigos.dedup();
+        if codigos.len() > 1 {
+            sequencia.reused.push(RepeatedNumber { numero: pad(numero, largura), occurrences: codigos });
+        } else if ocorrencias.len() > 1 {
+            sequencia.duplicates.push(RepeatedNumber {
+                numero: pad(numero, largura),
+                occurrences: ocorrencias.iter().map(|o| o.file.clone()).collect(),
+            });
+        }
+
+        // Número maior emitido antes de um número menor
+        let emissao = ocorrencias.iter().find_map(|o| {
+            let texto = o.emissao.clone()?;
+            let data = dates::parse(&texto)?.local;
+            Some((texto, data))
+        });
+        let Some((texto, data)) = emissao else {
+            continue;
+        };
+        if let Some((numero_anterior, texto_anterior, data_anterior)) = &emitida_antes {
+            if data < *data_anterior {
+                sequencia.out_of_order.push(OutOfOrder {
+                    numero: pad(numero, largura),
+                    data_emissao: texto,
+                    previous_numero: pad(*numero_anterior, largura),
+                    previous_data_emissao: texto_anterior.clone(),
+                });
+                continue;
+            }
+        }
+        emitida_antes = Some((numero, texto, data));
+    }
+    sequencia
+}
+
+// Agrupar por prestador (CNPJ/CPF) e analisar cada sequência
+pub(crate) fn analyze(entries: &[MergeEntry]) -> Vec<PrestadorSequence> {
+    let mut prestadores: BTreeMap<String, Vec<&MergeEntry>> = BTreeMap::new();
+    for entry in entries {
+        let prestador = str_or(&entry.nota, "PrestadorServico/IdentificacaoPrestador/Cnpj", "");
+        prestadores.entry(prestador).or_default().push(entry);
+    }
+    prestadores
+        .into_iter()
+        .map(|(prestador, entries)| analyze_prestador(prestador, &entries))
+        .collect()
+}
@@ -0,0 +1,38 @@
+// Layout ABRASF 1.0: ConsultarNfseResposta -> ListaNfse -> CompNfse -> Nfse -> InfNfse
+use serde_json::Value;
+
+use super::{ProviderAdapter, Record};
+use crate::nota;
+
+pub(crate) struct Abrasf;
+
+// Extrair as notas de uma lista de CompNfse (compartilhado com os provedores derivados do ABRASF)
+pub(crate) fn extract_comp_nfse(lista: &Value) -> Vec<Record> {
+    nota::as_list(lista)
+        .iter()
+        .map(|comp| {
+            let inf = nota::get_path(comp, "Nfse/InfNfse")
+                .cloned()
+                .ok_or_else(|| "Estrutura InfNfse não encontrada".to_string());
+            Record::new("CompNfse", comp, inf)
+        })
+        .collect()
+}
+
+impl ProviderAdapter for Abrasf {
+    fn name(&self) -> &'static str {
+        "ABRASF"
+    }
+
+    fn detect(&self, root: roxmltree::Node) -> bool {
+        root.tag_name().name() == "ConsultarNfseResposta"
+    }
+
+    fn records(&self, tree: &Value) -> Result<Vec<Record>, String> {
+        // Navegar para ConsultarNfseResposta -> ListaNfse -> CompNfse
+        let lista_nfse = nota::get_path(tree, "ConsultarNfseResposta/ListaNfse/CompNfse")
+            .ok_or("Não foram encontradas notas fiscais no XML")?;
+
+        Ok(extract_comp_nfse(lista_nfse))
+    }
+}
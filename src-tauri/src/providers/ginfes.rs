@@ -0,0 +1,36 @@
+// GINFES (ABRASF 1.0 com namespaces próprios). Além da consulta por período, aceita as
+// respostas de consulta por lote e por RPS, onde os CompNfse ficam em outro nível.
+use serde_json::Value;
+
+use super::abrasf::extract_comp_nfse;
+use super::{ProviderAdapter, Record};
+use crate::nota;
+
+const GINFES_NAMESPACE_PREFIX: &str = "http://www.ginfes.com.br/";
+
+pub(crate) struct Ginfes;
+
+impl ProviderAdapter for Ginfes {
+    fn name(&self) -> &'static str {
+        "GINFES"
+    }
+
+    fn detect(&self, root: roxmltree::Node) -> bool {
+        root.tag_name()
+            .namespace()
+            .is_some_and(|ns| ns.starts_with(GINFES_NAMESPACE_PREFIX))
+    }
+
+    fn records(&self, tree: &Value) -> Result<Vec<Record>, String> {
+        let lista = [
+            "ConsultarNfseResposta/ListaNfse/CompNfse",
+            "ConsultarLoteRpsResposta/ListaNfse/CompNfse",
+            "ConsultarNfseRpsResposta/CompNfse",
+        ]
+        .iter()
+        .find_map(|path| nota::get_path(tree, path))
+        .ok_or("Não foram encontradas notas fiscais no XML")?;
+
+        Ok(extract_comp_nfse(lista))
+    }
+}
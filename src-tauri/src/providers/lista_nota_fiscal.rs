@@ -0,0 +1,34 @@
+// ListaNotaFiscal gerada por este conversor (ou exportada por ERPs no mesmo formato):
+// ListaNotaFiscal -> Nfse -> InfNfse, já com os nomes de elementos do ABRASF.
+use serde_json::Value;
+
+use super::{ProviderAdapter, Record};
+use crate::nota;
+
+pub(crate) struct ListaNotaFiscal;
+
+impl ProviderAdapter for ListaNotaFiscal {
+    fn name(&self) -> &'static str {
+        "ListaNotaFiscal"
+    }
+
+    fn detect(&self, root: roxmltree::Node) -> bool {
+        root.tag_name().name() == "ListaNotaFiscal"
+    }
+
+    fn records(&self, tree: &Value) -> Result<Vec<Record>, String> {
+        let lista = nota::get_path(tree, "ListaNotaFiscal/Nfse")
+            .ok_or("Não foram encontradas notas fiscais no XML")?;
+
+        Ok(nota::as_list(lista)
+            .iter()
+            .map(|nfse| {
+                let inf = nfse
+                    .get("InfNfse")
+                    .cloned()
+                    .ok_or_else(|| "Estrutura InfNfse não encontrada".to_string());
+                Record::new("Nfse", nfse, inf)
+            })
+            .collect())
+    }
+}
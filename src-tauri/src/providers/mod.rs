@@ -0,0 +1,73 @@
+// Adaptadores de provedor: cada layout municipal reconhece o próprio XML e entrega
+// as notas no formato InfNfse do ABRASF, que o perfil de mapeamento embutido converte
+// para o modelo interno (ver nota.rs e mapping.rs). Para suportar uma nova cidade basta
+// criar um módulo com o adaptador e registrá-lo em ADAPTERS.
+use serde_json::Value;
+
+use crate::cancellation::{self, NotaStatus};
+use crate::nota;
+
+mod abrasf;
+mod ginfes;
+mod lista_nota_fiscal;
+pub(crate) mod nacional;
+mod paulistana;
+
+pub(crate) trait ProviderAdapter {
+    // Nome exibido em mensagens e relatórios
+    fn name(&self) -> &'static str;
+
+    // Verificar se o documento pertence a este provedor (elemento raiz e namespace)
+    fn detect(&self, root: roxmltree::Node) -> bool;
+
+    // Extrair as notas da árvore gerada por parse_xml_to_json, um registro por nota
+    fn records(&self, tree: &Value) -> Result<Vec<Record>, String>;
+}
+
+// Uma nota do documento de origem: o elemento original (CompNfse, NFe...) e a InfNfse ABRASF
+// extraída dele. Falhas ficam no próprio registro para que as demais notas sigam convertendo.
+pub(crate) struct Record {
+    pub(crate) element: String,
+    pub(crate) source: Value,
+    pub(crate) inf: Result<Value, String>,
+    // Cancelamento/substituição lidos do elemento original
+    pub(crate) status: NotaStatus,
+}
+
+impl Record {
+    pub(crate) fn new(element: &str, source: &Value, inf: Result<Value, String>) -> Self {
+        Record { element: element.to_string(), source: source.clone(), inf, status: cancellation::read(source) }
+    }
+
+    // Número da nota para relatórios, mesmo quando a extração falhou
+    pub(crate) fn numero(&self) -> Option<String> {
+        match &self.inf {
+            Ok(inf) => nota::text_at(inf, "Numero"),
+            Err(_) => find_text(&self.source, &["Numero", "NumeroNFe", "nNFSe", "nDPS"]),
+        }
+    }
+}
+
+fn find_text(value: &Value, keys: &[&str]) -> Option<String> {
+    match value {
+        Value::Object(map) => keys
+            .iter()
+            .find_map(|key| map.get(*key).and_then(Value::as_str).map(str::to_string))
+            .or_else(|| map.values().find_map(|child| find_text(child, keys))),
+        Value::Array(items) => items.iter().find_map(|item| find_text(item, keys)),
+        _ => None,
+    }
+}
+
+// Ordem importa: adaptadores específicos antes do ABRASF genérico
+const ADAPTERS: &[&dyn ProviderAdapter] = &[
+    &ginfes::Ginfes,
+    &nacional::Nacional,
+    &paulistana::Paulistana,
+    &lista_nota_fiscal::ListaNotaFiscal,
+    &abrasf::Abrasf,
+];
+
+pub(crate) fn detect(root: roxmltree::Node) -> Option<&'static dyn ProviderAdapter> {
+    ADAPTERS.iter().copied().find(|adapter| adapter.detect(root))
+}
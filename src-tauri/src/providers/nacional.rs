@@ -0,0 +1,142 @@
+// NFS-e Padrão Nacional (leiaute 1.00): NFSe -> infNFSe (dados gerados pelo sistema) e
+// NFSe -> infNFSe -> DPS -> infDPS (declaração do prestador). Também aceita a DPS sozinha.
+// Cada documento é remontado no formato InfNfse do ABRASF antes da normalização.
+use serde_json::{json, Map, Value};
+
+use super::{ProviderAdapter, Record};
+use crate::nota::{self, text_at};
+
+pub(crate) const NACIONAL_NAMESPACE: &str = "http://www.sped.fazenda.gov.br/nfse";
+
+pub(crate) struct Nacional;
+
+fn first_text(value: &Value, paths: &[&str]) -> Option<String> {
+    paths.iter().find_map(|path| text_at(value, path))
+}
+
+// tpRetISSQN: 1 = não retido, 2 = retido pelo tomador, 3 = retido pelo intermediário
+fn iss_retido(tp_ret_issqn: Option<String>) -> Option<&'static str> {
+    tp_ret_issqn.map(|tipo| if tipo == "1" { "2" } else { "1" })
+}
+
+// opSimpNac: 1 = não optante, 2 = MEI, 3 = ME/EPP
+fn optante_simples(op_simp_nac: Option<String>) -> Option<&'static str> {
+    op_simp_nac.map(|opcao| if opcao == "1" { "2" } else { "1" })
+}
+
+fn to_inf_nfse(inf_nfse: &Value, inf_dps: &Value) -> Value {
+    let valores_dps = inf_dps.get("valores").cloned().unwrap_or(Value::Null);
+
+    // A chave de acesso (Id sem o prefixo "NFS") substitui o código de verificação
+    let chave_acesso = text_at(inf_nfse, "@_Id").map(|id| id.trim_start_matches("NFS").to_string());
+
+    json!({
+        "@_Id": text_at(inf_nfse, "@_Id").or_else(|| text_at(inf_dps, "@_Id")),
+        "Numero": text_at(inf_nfse, "nNFSe").or_else(|| text_at(inf_dps, "nDPS")),
+        "CodigoVerificacao": chave_acesso,
+        "DataEmissao": text_at(inf_dps, "dhEmi").or_else(|| text_at(inf_nfse, "dhProc")),
+        "Competencia": text_at(inf_dps, "dCompet"),
+        "OptanteSimplesNacional": optante_simples(text_at(inf_dps, "prest/regTrib/opSimpNac")),
+        "Servico": {
+            "Valores": {
+                "ValorServicos": text_at(&valores_dps, "vServPrest/vServ"),
+                "ValorDeducoes": text_at(&valores_dps, "vDedRed/vDR"),
+                "ValorPis": text_at(&valores_dps, "trib/tribFed/piscofins/vPis"),
+                "ValorCofins": text_at(&valores_dps, "trib/tribFed/piscofins/vCofins"),
+                "ValorInss": text_at(&valores_dps, "trib/tribFed/vRetCP"),
+                "ValorIr": text_at(&valores_dps, "trib/tribFed/vRetIRRF"),
+                "ValorCsll": text_at(&valores_dps, "trib/tribFed/vRetCSLL"),
+                "IssRetido": iss_retido(text_at(&valores_dps, "trib/tribMun/tpRetISSQN")),
+                "ValorIss": text_at(inf_nfse, "valores/vISSQN"),
+                "BaseCalculo": text_at(inf_nfse, "valores/vBC"),
+                "Aliquota": text_at(inf_nfse, "valores/pAliqAplic")
+                    .or_else(|| text_at(&valores_dps, "trib/tribMun/pAliq")),
+                "ValorLiquidoNfse": text_at(inf_nfse, "valores/vLiq"),
+                "DescontoIncondicionado": text_at(&valores_dps, "vDescCondIncond/vDescIncond"),
+                "DescontoCondicionado": text_at(&valores_dps, "vDescCondIncond/vDescCond")
+            },
+            // cTribNac completo (item, subitem e desdobro); a normalização aplica o formato do perfil
+            "ItemListaServico": text_at(inf_dps, "serv/cServ/cTribNac"),
+            "Discriminacao": text_at(inf_dps, "serv/cServ/xDescServ"),
+            "CodigoMunicipio": text_at(inf_dps, "serv/locPrest/cLocPrestacao")
+        },
+        "PrestadorServico": {
+            "IdentificacaoPrestador": {
+                "Cnpj": first_text(inf_nfse, &["emit/CNPJ", "emit/CPF"])
+                    .or_else(|| first_text(inf_dps, &["prest/CNPJ", "prest/CPF"])),
+                "InscricaoMunicipal": text_at(inf_nfse, "emit/IM").or_else(|| text_at(inf_dps, "prest/IM"))
+            },
+            "RazaoSocial": text_at(inf_nfse, "emit/xNome").or_else(|| text_at(inf_dps, "prest/xNome")),
+            "Endereco": {
+                "Endereco": text_at(inf_nfse, "emit/enderNac/xLgr"),
+                "Numero": text_at(inf_nfse, "emit/enderNac/nro"),
+                "Bairro": text_at(inf_nfse, "emit/enderNac/xBairro"),
+                "CodigoMunicipio": text_at(inf_nfse, "emit/enderNac/cMun"),
+                "Uf": text_at(inf_nfse, "emit/enderNac/UF"),
+                "Cep": text_at(inf_nfse, "emit/enderNac/CEP")
+            },
+            "Contato": {
+                "Telefone": text_at(inf_nfse, "emit/fone").or_else(|| text_at(inf_dps, "prest/fone")),
+                "Email": text_at(inf_nfse, "emit/email").or_else(|| text_at(inf_dps, "prest/email"))
+            }
+        },
+        "TomadorServico": {
+            "IdentificacaoTomador": {
+                "CpfCnpj": { "Cnpj": first_text(inf_dps, &["toma/CNPJ", "toma/CPF"]) },
+                "InscricaoMunicipal": text_at(inf_dps, "toma/IM")
+            },
+            "RazaoSocial": text_at(inf_dps, "toma/xNome"),
+            "Endereco": {
+                "Endereco": text_at(inf_dps, "toma/end/xLgr"),
+                "Numero": text_at(inf_dps, "toma/end/nro"),
+                "Complemento": text_at(inf_dps, "toma/end/xCpl"),
+                "Bairro": text_at(inf_dps, "toma/end/xBairro"),
+                "CodigoMunicipio": text_at(inf_dps, "toma/end/endNac/cMun"),
+                "Cep": text_at(inf_dps, "toma/end/endNac/CEP")
+            },
+            "Contato": {
+                "Telefone": text_at(inf_dps, "toma/fone"),
+                "Email": text_at(inf_dps, "toma/email")
+            }
+        },
+        "OrgaoGerador": {
+            "CodigoMunicipio": text_at(inf_dps, "cLocEmi"),
+            "Uf": text_at(inf_nfse, "emit/enderNac/UF")
+        }
+    })
+}
+
+fn extract_inf_nfse(tree: &Value) -> Result<Value, String> {
+    let empty_object = Value::Object(Map::new());
+
+    let (inf_nfse, inf_dps) = if let Some(inf_nfse) = nota::get_path(tree, "NFSe/infNFSe") {
+        let inf_dps = nota::get_path(inf_nfse, "DPS/infDPS").ok_or("Estrutura infDPS não encontrada")?;
+        (inf_nfse, inf_dps)
+    } else {
+        // DPS ainda sem NFS-e gerada: só existem os dados declarados pelo prestador
+        let inf_dps = nota::get_path(tree, "DPS/infDPS").ok_or("Estrutura infNFSe não encontrada")?;
+        (&empty_object, inf_dps)
+    };
+
+    Ok(to_inf_nfse(inf_nfse, inf_dps))
+}
+
+impl ProviderAdapter for Nacional {
+    fn name(&self) -> &'static str {
+        "NFS-e Padrão Nacional"
+    }
+
+    fn detect(&self, root: roxmltree::Node) -> bool {
+        root.tag_name().namespace() == Some(NACIONAL_NAMESPACE)
+            && matches!(root.tag_name().name(), "NFSe" | "DPS")
+    }
+
+    fn records(&self, tree: &Value) -> Result<Vec<Record>, String> {
+        // Cada documento do Padrão Nacional traz uma única nota
+        let (element, source) = tree
+            .as_object()
+            .and_then(|map| map.iter().next())
+            .ok_or("Não foram encontradas notas fiscais no XML")?;
+        Ok(vec![Record::new(element, source, extract_inf_nfse(tree))])
+    }
+}
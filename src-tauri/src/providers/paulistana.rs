@@ -0,0 +1,119 @@
+// NFS-e Paulistana (São Paulo): RetornoConsulta/RetornoXML -> NFe, com campos planos
+// e nomes próprios. Cada NFe é remontada no formato InfNfse do ABRASF antes da normalização.
+use serde_json::{json, Value};
+
+use super::{ProviderAdapter, Record};
+use crate::nota::{self, text_at};
+
+const PAULISTANA_NAMESPACE: &str = "http://www.prefeitura.sp.gov.br/nfe";
+const CODIGO_MUNICIPIO_SAO_PAULO: &str = "3550308";
+
+pub(crate) struct Paulistana;
+
+// CPFCNPJPrestador / CPFCNPJTomador trazem CNPJ ou CPF
+fn cpf_cnpj(nfe: &Value, campo: &str) -> Option<String> {
+    text_at(nfe, &format!("{}/CNPJ", campo)).or_else(|| text_at(nfe, &format!("{}/CPF", campo)))
+}
+
+fn endereco(nfe: &Value, campo: &str) -> Value {
+    // O logradouro vem separado do tipo (R, AV, ...)
+    let logradouro = [
+        text_at(nfe, &format!("{}/TipoLogradouro", campo)),
+        text_at(nfe, &format!("{}/Logradouro", campo)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    json!({
+        "Endereco": logradouro,
+        "Numero": text_at(nfe, &format!("{}/NumeroEndereco", campo)),
+        "Complemento": text_at(nfe, &format!("{}/ComplementoEndereco", campo)),
+        "Bairro": text_at(nfe, &format!("{}/Bairro", campo)),
+        "CodigoMunicipio": text_at(nfe, &format!("{}/Cidade", campo)),
+        "Uf": text_at(nfe, &format!("{}/UF", campo)),
+        "Cep": text_at(nfe, &format!("{}/CEP", campo))
+    })
+}
+
+// ISSRetido vem como booleano; no ABRASF 1 = sim e 2 = não
+fn iss_retido(nfe: &Value) -> Option<&'static str> {
+    match text_at(nfe, "ISSRetido")?.trim() {
+        "true" | "1" | "S" => Some("1"),
+        _ => Some("2"),
+    }
+}
+
+fn to_inf_nfse(nfe: &Value) -> Value {
+    json!({
+        "Numero": text_at(nfe, "ChaveNFe/NumeroNFe"),
+        "CodigoVerificacao": text_at(nfe, "ChaveNFe/CodigoVerificacao"),
+        "DataEmissao": text_at(nfe, "DataEmissaoNFe"),
+        "Competencia": text_at(nfe, "DataFatoGeradorNFe"),
+        "OptanteSimplesNacional": text_at(nfe, "OpcaoSimples").map(|opcao| if opcao == "0" { "2" } else { "1" }),
+        "Servico": {
+            "Valores": {
+                "ValorServicos": text_at(nfe, "ValorServicos"),
+                "ValorDeducoes": text_at(nfe, "ValorDeducoes"),
+                "ValorPis": text_at(nfe, "ValorPIS"),
+                "ValorCofins": text_at(nfe, "ValorCOFINS"),
+                "ValorInss": text_at(nfe, "ValorINSS"),
+                "ValorIr": text_at(nfe, "ValorIR"),
+                "ValorCsll": text_at(nfe, "ValorCSLL"),
+                "IssRetido": iss_retido(nfe),
+                "ValorIss": text_at(nfe, "ValorISS"),
+                "Aliquota": text_at(nfe, "AliquotaServicos")
+            },
+            "ItemListaServico": text_at(nfe, "CodigoServico"),
+            "Discriminacao": text_at(nfe, "Discriminacao"),
+            "CodigoMunicipio": CODIGO_MUNICIPIO_SAO_PAULO
+        },
+        "PrestadorServico": {
+            "IdentificacaoPrestador": {
+                "Cnpj": cpf_cnpj(nfe, "CPFCNPJPrestador"),
+                "InscricaoMunicipal": text_at(nfe, "ChaveNFe/InscricaoPrestador")
+            },
+            "RazaoSocial": text_at(nfe, "RazaoSocialPrestador"),
+            "Endereco": endereco(nfe, "EnderecoPrestador"),
+            "Contato": { "Email": text_at(nfe, "EmailPrestador") }
+        },
+        "TomadorServico": {
+            "IdentificacaoTomador": {
+                "CpfCnpj": { "Cnpj": cpf_cnpj(nfe, "CPFCNPJTomador") },
+                "InscricaoMunicipal": text_at(nfe, "InscricaoMunicipalTomador")
+            },
+            "RazaoSocial": text_at(nfe, "RazaoSocialTomador"),
+            "Endereco": endereco(nfe, "EnderecoTomador"),
+            "Contato": { "Email": text_at(nfe, "EmailTomador") }
+        },
+        "OrgaoGerador": {
+            "CodigoMunicipio": CODIGO_MUNICIPIO_SAO_PAULO,
+            "Uf": "SP"
+        }
+    })
+}
+
+impl ProviderAdapter for Paulistana {
+    fn name(&self) -> &'static str {
+        "NFS-e Paulistana"
+    }
+
+    fn detect(&self, root: roxmltree::Node) -> bool {
+        root.tag_name().namespace() == Some(PAULISTANA_NAMESPACE)
+    }
+
+    fn records(&self, tree: &Value) -> Result<Vec<Record>, String> {
+        // O elemento raiz varia conforme o serviço consultado (RetornoConsulta, RetornoXML...)
+        let lista = tree
+            .as_object()
+            .and_then(|map| map.values().next())
+            .and_then(|retorno| retorno.get("NFe"))
+            .ok_or("Não foram encontradas notas fiscais no XML")?;
+
+        Ok(nota::as_list(lista)
+            .iter()
+            .map(|nfe| Record::new("NFe", nfe, Ok(to_inf_nfse(nfe))))
+            .collect())
+    }
+}
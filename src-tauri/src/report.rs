@@ -0,0 +1,232 @@
+// Relatório de conversão: para cada nota, o que foi inventado (valores padrão), o que ficou
+// de fora, o que foi reformatado e os avisos. Pode ser gravado em JSON ou CSV ao lado da saída.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cancellation::NotaStatus;
+use crate::merge::Duplicate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct FieldValue {
+    pub(crate) field: String,
+    pub(crate) value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Coercion {
+    pub(crate) field: String,
+    pub(crate) from: String,
+    pub(crate) to: Value,
+}
+
+// Valor que não confere com os demais (ex.: ValorIss diferente de BaseCalculo x Aliquota)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Inconsistency {
+    pub(crate) field: String,
+    pub(crate) expected: f64,
+    pub(crate) found: f64,
+}
+
+// Código de município da nota com o nome da tabela do IBGE (ausente se não constar dela)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Municipality {
+    pub(crate) field: String,
+    pub(crate) code: String,
+    pub(crate) name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct NotaReport {
+    pub(crate) numero: String,
+    // Campos ausentes na origem, preenchidos com valor padrão
+    pub(crate) defaulted: Vec<FieldValue>,
+    // Valores da origem que não chegaram à saída (sem mapeamento ou inválidos)
+    pub(crate) dropped: Vec<FieldValue>,
+    pub(crate) coerced: Vec<Coercion>,
+    pub(crate) warnings: Vec<String>,
+    pub(crate) inconsistencies: Vec<Inconsistency>,
+    pub(crate) municipalities: Vec<Municipality>,
+    // Cancelamento e substituição informados na origem ou ligados pelo lote
+    pub(crate) status: NotaStatus,
+}
+
+// Evento sem efeito na situação (manifestação, análise fiscal) ou sobre nota fora do lote
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct IgnoredEvent {
+    pub(crate) chave: String,
+    pub(crate) event: String,
+    pub(crate) reason: String,
+}
+
+// Nota (ou documento inteiro) que não pôde ser convertida no modo de sucesso parcial
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Failure {
+    // Posição da nota na entrada, a partir de 1; ausente quando o documento todo falhou
+    pub(crate) index: Option<usize>,
+    pub(crate) numero: Option<String>,
+    pub(crate) reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct ConversionReport {
+    pub(crate) notas: Vec<NotaReport>,
+    pub(crate) failures: Vec<Failure>,
+    // Na junção de arquivos, as notas repetidas que ficaram de fora
+    pub(crate) duplicates: Vec<Duplicate>,
+    // Notas convertidas que o filtro deixou fora da saída
+    pub(crate) filtered_out: usize,
+    // Eventos do ADN que não mudaram nenhuma nota
+    pub(crate) ignored_events: Vec<IgnoredEvent>,
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl NotaReport {
+    // Chamado só para campos ausentes na origem: zero e texto vazio também são invenção.
+    // Nulo não gera valor na saída e fica de fora. Campo preenchido de novo por uma etapa
+    // seguinte (a UF vazia do mapeamento completada pelo município) fica com o valor final.
+    pub(crate) fn defaulted(&mut self, field: &str, value: &Value) {
+        if value.is_null() {
+            return;
+        }
+        match self.defaulted.iter_mut().find(|anterior| anterior.field == field) {
+            Some(anterior) => anterior.value = value.clone(),
+            None => self.defaulted.push(FieldValue { field: field.to_string(), value: value.clone() }),
+        }
+    }
+
+    pub(crate) fn dropped(&mut self, field: &str, value: &Value) {
+        self.dropped.push(FieldValue { field: field.to_string(), value: value.clone() });
+    }
+
+    pub(crate) fn coerced(&mut self, field: &str, from: &str, to: &Value) {
+        self.coerced.push(Coercion { field: field.to_string(), from: from.to_string(), to: to.clone() });
+    }
+
+    pub(crate) fn inconsistent(&mut self, field: &str, expected: f64, found: f64) {
+        // Esperado em centavos, como nos documentos
+        let expected = (expected * 100.0).round() / 100.0;
+        self.inconsistencies.push(Inconsistency { field: field.to_string(), expected, found });
+    }
+
+    pub(crate) fn municipality(&mut self, field: &str, code: &str, name: Option<String>) {
+        self.municipalities.push(Municipality { field: field.to_string(), code: code.to_string(), name });
+    }
+
+    pub(crate) fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+}
+
+// Campo de CSV separado por ";" (padrão do Excel em português)
+pub(crate) fn csv_field(text: &str) -> String {
+    if text.contains([';', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+impl ConversionReport {
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    // Uma linha por ocorrência: Numero;Ocorrencia;Campo;Detalhe
+    pub(crate) fn to_csv(&self) -> String {
+        let mut linhas = vec!["Numero;Ocorrencia;Campo;Detalhe".to_string()];
+        for nota in &self.notas {
+            let mut linha = |ocorrencia: &str, campo: &str, detalhe: String| {
+                linhas.push(
+                    [nota.numero.as_str(), ocorrencia, campo, detalhe.as_str()]
+                        .map(csv_field)
+                        .join(";"),
+                );
+            };
+            for item in &nota.defaulted {
+                linha("valor padrão", &item.field, value_text(&item.value));
+            }
+            for item in &nota.dropped {
+                linha("descartado", &item.field, value_text(&item.value));
+            }
+            for item in &nota.coerced {
+                linha("convertido", &item.field, format!("{} -> {}", item.from, value_text(&item.to)));
+            }
+            for aviso in &nota.warnings {
+                linha("aviso", "", aviso.clone());
+            }
+            for item in &nota.inconsistencies {
+                linha("inconsistente", &item.field, format!("esperado {:.2}, informado {:.2}", item.expected, item.found));
+            }
+            if nota.status.cancelled {
+                let detalhe = [nota.status.cancelled_at.as_deref(), nota.status.cancellation_code.as_deref().map(|codigo| format!("código {}", codigo)).as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                linha("cancelada", "", detalhe);
+            }
+            if let Some(substituta) = &nota.status.substituted_by {
+                linha("substituída", "", format!("pela nota {}", substituta));
+            }
+            for item in &nota.municipalities {
+                let nome = item.name.as_deref().unwrap_or("(fora da tabela)");
+                linha("município", &item.field, format!("{} {}", item.code, nome));
+            }
+        }
+        for falha in &self.failures {
+            let posicao = falha.index.map(|index| format!("posição {}: ", index)).unwrap_or_default();
+            linhas.push(
+                [falha.numero.as_deref().unwrap_or(""), "rejeitada", "", &format!("{}{}", posicao, falha.reason)]
+                    .map(csv_field)
+                    .join(";"),
+            );
+        }
+        for duplicada in &self.duplicates {
+            let (ocorrencia, detalhe) = if duplicada.conflicts.is_empty() {
+                ("duplicada", format!("mantida de {}, repetida em {}", duplicada.kept_file, duplicada.dropped_file))
+            } else {
+                ("conflito", format!("mantida de {}, descartada de {}: {}", duplicada.kept_file, duplicada.dropped_file, duplicada.conflicts.join(", ")))
+            };
+            linhas.push([duplicada.numero.as_str(), ocorrencia, "", &detalhe].map(csv_field).join(";"));
+        }
+        for evento in &self.ignored_events {
+            let detalhe = format!("{} da chave {}: {}", evento.event, evento.chave, evento.reason);
+            linhas.push(["", "evento ignorado", "", &detalhe].map(csv_field).join(";"));
+        }
+        linhas.join("\r\n") + "\r\n"
+    }
+
+    // Gravar como <nome da saída>-relatorio.<ext> na mesma pasta
+    pub(crate) fn save(&self, output_path: &Path, format: ReportFormat) -> Result<PathBuf, String> {
+        match format {
+            ReportFormat::Json => write_sibling(output_path, "relatorio", "json", self.to_json()?),
+            ReportFormat::Csv => write_sibling(output_path, "relatorio", "csv", self.to_csv()),
+        }
+    }
+}
+
+// Arquivo auxiliar ao lado da saída: <nome da saída>-<sufixo>.<ext>
+pub(crate) fn write_sibling(output_path: &Path, suffix: &str, extension: &str, content: String) -> Result<PathBuf, String> {
+    let stem = output_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "notas".to_string());
+    let path = output_path.with_file_name(format!("{}-{}.{}", stem, suffix, extension));
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(path)
+}
@@ -0,0 +1,199 @@
+// Código do serviço (ItemListaServico): leitura das variações enviadas pelos fornecedores
+// ("10.05", "1005", "10.5", "100501"), conferência com a lista da LC 116/2003 e escrita no
+// formato configurado. O cTribNac do Padrão Nacional desdobra cada subitem da LC 116 em mais
+// dois dígitos; sem desdobro informado, vale o 01. A tabela embutida (tables/ctribnac.json) só
+// traz a descrição (xTribNac) de alguns códigos e pode ser substituída pela lista nacional
+// completa; código fora dela não é erro.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::OnceLock;
+
+use crate::document_id::strip_mask;
+use crate::nota::{set_path, text_at};
+use crate::report::NotaReport;
+
+const ITEM_PATH: &str = "Servico/ItemListaServico";
+const CNAE_PATH: &str = "Servico/CodigoCnae";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ItemFormat {
+    // 1005
+    Compact,
+    // 10.05
+    Dotted,
+    // 100501 (cTribNac)
+    National,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ServiceSettings {
+    pub(crate) format: ItemFormat,
+    // CNAE (só dígitos ou com máscara) -> item da lista, para notas sem ItemListaServico
+    pub(crate) cnae_items: BTreeMap<String, String>,
+}
+
+impl Default for ServiceSettings {
+    fn default() -> Self {
+        ServiceSettings { format: ItemFormat::Compact, cnae_items: BTreeMap::new() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ServiceCode {
+    pub(crate) item: u32,
+    pub(crate) subitem: u32,
+    // Desdobro nacional; ausente nos códigos da LC 116
+    pub(crate) desdobro: Option<u32>,
+}
+
+fn lc116() -> &'static HashSet<String> {
+    static LISTA: OnceLock<HashSet<String>> = OnceLock::new();
+    LISTA.get_or_init(|| {
+        serde_json::from_str(include_str!("../tables/lc116.json")).expect("tabela da LC 116 embutida inválida")
+    })
+}
+
+fn national_table() -> &'static BTreeMap<String, String> {
+    static TABELA: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+    TABELA.get_or_init(|| {
+        serde_json::from_str(include_str!("../tables/ctribnac.json")).expect("tabela do cTribNac embutida inválida")
+    })
+}
+
+// Descrição do cTribNac (xTribNac), quando consta da tabela
+pub(crate) fn national_description(codigo: &str) -> Option<&'static str> {
+    national_table().get(codigo).map(String::as_str)
+}
+
+fn number(text: &str) -> Option<u32> {
+    if text.is_empty() || text.len() > 2 || !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+impl ServiceCode {
+    // "10.05", "10.5", "10.05.01", "1005", "105" (1.05), "100501"
+    pub(crate) fn parse(text: &str) -> Option<ServiceCode> {
+        let text = text.trim();
+        let partes: Vec<&str> = if text.contains('.') {
+            text.split('.').collect()
+        } else if text.chars().all(|c| c.is_ascii_digit()) {
+            match text.len() {
+                3 => vec![&text[..1], &text[1..]],
+                4 => vec![&text[..2], &text[2..]],
+                6 => vec![&text[..2], &text[2..4], &text[4..]],
+                _ => return None,
+            }
+        } else {
+            return None;
+        };
+
+        match partes.as_slice() {
+            [item, subitem] => Some(ServiceCode { item: number(item)?, subitem: number(subitem)?, desdobro: None }),
+            [item, subitem, desdobro] if desdobro.len() == 2 => Some(ServiceCode {
+                item: number(item)?,
+                subitem: number(subitem)?,
+                desdobro: Some(number(desdobro)?),
+            }),
+            _ => None,
+        }
+    }
+
+    // Item e subitem constam da LC 116 (subitens vetados ficam de fora)
+    pub(crate) fn exists(&self) -> bool {
+        lc116().contains(&format!("{:02}.{:02}", self.item, self.subitem))
+    }
+
+    // cTribNac: subitem da LC 116 seguido do desdobro (01 quando não informado)
+    pub(crate) fn national(&self) -> String {
+        format!("{:02}{:02}{:02}", self.item, self.subitem, self.desdobro.unwrap_or(1))
+    }
+
+    pub(crate) fn format(&self, format: ItemFormat) -> String {
+        match format {
+            ItemFormat::Compact => format!("{:02}{:02}", self.item, self.subitem),
+            ItemFormat::Dotted => format!("{:02}.{:02}", self.item, self.subitem),
+            ItemFormat::National => self.national(),
+        }
+    }
+}
+
+impl ServiceSettings {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for (cnae, item) in &self.cnae_items {
+            match ServiceCode::parse(item) {
+                Some(codigo) if codigo.exists() => {}
+                _ => return Err(format!("CNAE {}: item {} não consta da LC 116", cnae, item)),
+            }
+        }
+        Ok(())
+    }
+
+    // Nota sem ItemListaServico: usar o item associado ao CNAE, se houver
+    pub(crate) fn fill_from_cnae(&self, nota: &mut Value, report: &mut NotaReport) {
+        if text_at(nota, ITEM_PATH).is_some_and(|item| !item.trim().is_empty()) {
+            return;
+        }
+        let Some(cnae) = text_at(nota, CNAE_PATH).map(|cnae| strip_mask(&cnae)).filter(|cnae| !cnae.is_empty()) else {
+            return;
+        };
+        let item = self
+            .cnae_items
+            .iter()
+            .find(|(chave, _)| strip_mask(chave) == cnae)
+            .map(|(_, item)| Value::String(item.clone()));
+        if let (Some(item), Some(campos)) = (item, nota.as_object_mut()) {
+            report.defaulted(ITEM_PATH, &item);
+            set_path(campos, ITEM_PATH, item);
+        }
+    }
+
+    // Reescrever o ItemListaServico no formato configurado; códigos fora da LC 116 ficam como
+    // vieram, com aviso
+    pub(crate) fn normalize_nota(&self, nota: &mut Value, report: &mut NotaReport) {
+        let Some(original) = text_at(nota, ITEM_PATH).filter(|s| !s.trim().is_empty()) else {
+            return;
+        };
+        let codigo = match ServiceCode::parse(&original) {
+            Some(codigo) if codigo.exists() => codigo,
+            _ => {
+                report.warn(format!("ItemListaServico {} não consta da LC 116/2003", original));
+                return;
+            }
+        };
+
+        let normalizado = codigo.format(self.format);
+        if normalizado != original {
+            let valor = Value::String(normalizado);
+            report.coerced(ITEM_PATH, &original, &valor);
+            if let Some(campos) = nota.as_object_mut() {
+                set_path(campos, ITEM_PATH, valor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemFormat, ServiceCode};
+
+    fn national(item: &str) -> String {
+        ServiceCode::parse(item).expect("item inválido").format(ItemFormat::National)
+    }
+
+    #[test]
+    fn national_code_from_lc116_items() {
+        assert_eq!(national("10.05"), "100501");
+        assert_eq!(national("1005"), "100501");
+        assert_eq!(national("10.5"), "100501");
+        assert_eq!(national("17.01"), "170101");
+        assert_eq!(national("1.05"), "010501");
+        assert_eq!(national("105"), "010501");
+        assert_eq!(national("100502"), "100502");
+        assert_eq!(national("14.01.03"), "140103");
+    }
+}
@@ -0,0 +1,138 @@
+// Desembrulhar respostas de webservice: o documento NFS-e real costuma vir dentro de
+// soap:Envelope/soap:Body/*Response/outputXML (ou <return>), como texto escapado ou CDATA.
+use crate::providers;
+
+const SOAP_11_NAMESPACE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP_12_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+fn is_envelope(node: roxmltree::Node) -> bool {
+    node.tag_name().name() == "Envelope"
+        && matches!(node.tag_name().namespace(), Some(SOAP_11_NAMESPACE | SOAP_12_NAMESPACE))
+}
+
+fn child_element<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+// Elementos de resposta que trazem o documento como texto escapado ou CDATA
+const WRAPPERS: &[&str] = &["outputXML", "return", "RetornoXML", "xmlRetorno"];
+
+// Wrappers conhecidos e o padrão *Result dos serviços .NET (ConsultarNfseResult)
+fn is_wrapper(node: roxmltree::Node) -> bool {
+    let nome = node.tag_name().name();
+    WRAPPERS.contains(&nome) || nome.ends_with("Result")
+}
+
+// Extrair os documentos contidos no XML. Um documento comum é devolvido intacto.
+pub(crate) fn unwrap_payloads(xml: &str) -> Result<Vec<String>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+
+    if !is_envelope(root) {
+        // Documento reconhecido ou sem XML embutido: segue como está
+        let mut payloads = Vec::new();
+        if providers::detect(root).is_none() {
+            collect_payloads(xml, root, &mut payloads);
+        }
+        if payloads.is_empty() {
+            payloads.push(xml.to_string());
+        }
+        return Ok(payloads);
+    }
+
+    let body = body(root)?;
+    let mut payloads = Vec::new();
+    collect_payloads(xml, body, &mut payloads);
+
+    if payloads.is_empty() {
+        return Err("Nenhum documento NFS-e encontrado no envelope SOAP".to_string());
+    }
+    Ok(payloads)
+}
+
+// Só o envelope, sem procurar NFS-e (exportações de XML qualquer): cada elemento do Body vira
+// um documento e o que não é envelope SOAP segue intacto
+pub(crate) fn unwrap_envelope(xml: &str) -> Result<Vec<String>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+    if !is_envelope(root) {
+        return Ok(vec![xml.to_string()]);
+    }
+    let documentos: Vec<String> = body(root)?
+        .children()
+        .filter(|n| n.is_element())
+        .map(|n| inline_document(xml, n))
+        .collect();
+    if documentos.is_empty() {
+        return Err("Envelope SOAP com Body vazio".to_string());
+    }
+    Ok(documentos)
+}
+
+// Body do envelope; um SOAP Fault vira erro com o motivo informado
+fn body<'a, 'input>(envelope: roxmltree::Node<'a, 'input>) -> Result<roxmltree::Node<'a, 'input>, String> {
+    let body = child_element(envelope, "Body").ok_or("Envelope SOAP sem Body")?;
+    if let Some(fault) = child_element(body, "Fault") {
+        // SOAP 1.1 usa faultstring; SOAP 1.2 usa Reason/Text
+        let motivo = child_element(fault, "faultstring")
+            .or_else(|| child_element(fault, "Reason").and_then(|reason| child_element(reason, "Text")))
+            .and_then(|node| node.text())
+            .unwrap_or("sem descrição");
+        return Err(format!("Webservice retornou SOAP Fault: {}", motivo.trim()));
+    }
+    Ok(body)
+}
+
+// Só vale como documento embutido o texto que é XML de um layout conhecido (ou que embrulha
+// um); texto comum com "<" ou HTML escapado continua sendo só texto
+fn embedded_payloads(text: &str) -> Vec<String> {
+    let Ok(doc) = roxmltree::Document::parse(text) else {
+        return Vec::new();
+    };
+    let root = doc.root_element();
+    if providers::detect(root).is_some() {
+        return vec![text.to_string()];
+    }
+    let mut payloads = Vec::new();
+    collect_payloads(text, root, &mut payloads);
+    payloads
+}
+
+fn collect_payloads(xml: &str, node: roxmltree::Node, payloads: &mut Vec<String>) {
+    for child in node.children().filter(|n| n.is_element()) {
+        if providers::detect(child).is_some() {
+            // Documento inline (não escapado) dentro do Body
+            payloads.push(inline_document(xml, child));
+        } else if child.children().any(|n| n.is_element()) {
+            collect_payloads(xml, child, payloads);
+        } else if is_wrapper(child) {
+            // outputXML/return: texto escapado ou CDATA, já desescapado pelo parser
+            let text: String = child.children().filter_map(|n| n.text()).collect();
+            let text = text.trim();
+            if text.starts_with('<') {
+                payloads.extend(embedded_payloads(text));
+            }
+        }
+    }
+}
+
+// Recortar o elemento do XML original, declarando os namespaces herdados dos ancestrais
+fn inline_document(xml: &str, node: roxmltree::Node) -> String {
+    let fragment = &xml[node.range()];
+    let tag_end = fragment.find(|c: char| c.is_whitespace() || c == '>' || c == '/').unwrap_or(fragment.len());
+    let start_tag = &fragment[..fragment.find('>').unwrap_or(fragment.len())];
+
+    let mut declarations = String::new();
+    for ns in node.namespaces().filter(|ns| ns.uri() != XML_NAMESPACE) {
+        let name = match ns.name() {
+            Some(prefix) => format!("xmlns:{}", prefix),
+            None => "xmlns".to_string(),
+        };
+        if !start_tag.contains(&format!(" {}=", name)) {
+            declarations.push_str(&format!(" {}=\"{}\"", name, ns.uri()));
+        }
+    }
+
+    format!("{}{}{}", &fragment[..tag_end], declarations, &fragment[tag_end..])
+}
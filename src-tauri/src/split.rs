@@ -0,0 +1,115 @@
+// Divisão da saída em grupos de notas (competência, prestador, tomador ou órgão gerador),
+// um arquivo por grupo com o nome montado a partir de um modelo como "{nome}-{competencia}".
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::dates;
+use crate::nota::text_at;
+
+pub(crate) const DEFAULT_TEMPLATE: &str = "{nome}-{grupo}";
+const CAMPOS_MODELO: &[&str] = &["nome", "grupo", "competencia", "prestador", "tomador", "orgao"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SplitKey {
+    // Mês da competência (AAAA-MM)
+    Competencia,
+    // CNPJ/CPF do prestador
+    Prestador,
+    // CNPJ/CPF do tomador
+    Tomador,
+    // Município do órgão gerador
+    OrgaoGerador,
+}
+
+// Valores do grupo disponíveis no modelo de nome (tirados da primeira nota)
+#[derive(Debug, Clone)]
+pub(crate) struct SplitGroup {
+    pub(crate) key: String,
+    fields: BTreeMap<&'static str, String>,
+}
+
+fn competencia(nota: &Value) -> Option<String> {
+    let texto = text_at(nota, "Competencia")?;
+    dates::parse(&texto).map(|data| data.local.format("%Y-%m").to_string())
+}
+
+fn template_fields(nota: &Value) -> BTreeMap<&'static str, String> {
+    let valores = [
+        ("competencia", competencia(nota)),
+        ("prestador", text_at(nota, "PrestadorServico/IdentificacaoPrestador/Cnpj")),
+        ("tomador", text_at(nota, "TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj")),
+        ("orgao", text_at(nota, "OrgaoGerador/CodigoMunicipio")),
+    ];
+    valores
+        .into_iter()
+        .map(|(campo, valor)| (campo, valor.filter(|v| !v.trim().is_empty()).unwrap_or_else(|| format!("sem-{}", campo))))
+        .collect()
+}
+
+impl SplitKey {
+    fn field(self) -> &'static str {
+        match self {
+            SplitKey::Competencia => "competencia",
+            SplitKey::Prestador => "prestador",
+            SplitKey::Tomador => "tomador",
+            SplitKey::OrgaoGerador => "orgao",
+        }
+    }
+}
+
+// Campos desconhecidos no modelo viram erro antes de gravar qualquer arquivo; sem o campo da
+// divisão no nome, grupos diferentes iriam para o mesmo arquivo. O modelo é só o nome: com
+// separador de pasta, unidade ou "..", o arquivo sairia fora da pasta escolhida.
+pub(crate) fn validate_template(template: &str, key: SplitKey) -> Result<(), String> {
+    if template.contains(['/', '\\', ':']) || template.contains("..") {
+        return Err(format!("O modelo de nome não pode conter pastas: {}", template));
+    }
+    let mut resto = template;
+    while let Some(inicio) = resto.find('{') {
+        let Some(fim) = resto[inicio..].find('}') else {
+            return Err(format!("Modelo de nome inválido: {}", template));
+        };
+        let campo = &resto[inicio + 1..inicio + fim];
+        if !CAMPOS_MODELO.contains(&campo) {
+            return Err(format!("Campo desconhecido no modelo de nome: {{{}}}", campo));
+        }
+        resto = &resto[inicio + fim + 1..];
+    }
+    if !template.contains("{grupo}") && !template.contains(&format!("{{{}}}", key.field())) {
+        return Err(format!("O modelo de nome precisa conter {{grupo}} ou {{{}}}", key.field()));
+    }
+    Ok(())
+}
+
+// Agrupar mantendo a ordem das notas dentro de cada grupo; os grupos saem em ordem de chave
+pub(crate) fn partition(notas: Vec<Value>, key: SplitKey) -> Vec<(SplitGroup, Vec<Value>)> {
+    let mut grupos: BTreeMap<String, (SplitGroup, Vec<Value>)> = BTreeMap::new();
+    for nota in notas {
+        let fields = template_fields(&nota);
+        let chave = fields[key.field()].clone();
+        grupos
+            .entry(chave.clone())
+            .or_insert_with(|| (SplitGroup { key: chave, fields }, Vec::new()))
+            .1
+            .push(nota);
+    }
+    grupos.into_values().collect()
+}
+
+// Caracteres fora de [A-Za-z0-9-_.] viram "_" para o nome valer em qualquer sistema
+fn sanitize(texto: &str) -> String {
+    texto
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect()
+}
+
+pub(crate) fn file_name(template: &str, nome: &str, group: &SplitGroup) -> String {
+    let mut resultado = template.replace("{nome}", nome).replace("{grupo}", &sanitize(&group.key));
+    for (campo, valor) in &group.fields {
+        resultado = resultado.replace(&format!("{{{}}}", campo), &sanitize(valor));
+    }
+    resultado
+}
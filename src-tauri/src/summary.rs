@@ -0,0 +1,148 @@
+// Resumo das notas exportadas: totais de ValorServicos, ValorIss e retenções federais por
+// mês de competência, por tomador e por item da lista de serviços. As somas usam decimal
+// exato (nada de f64) para bater centavo a centavo com a contabilidade.
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::dates;
+use crate::nota::text_at;
+use crate::report::{csv_field, write_sibling, ReportFormat};
+
+const VALORES: &[&str] = &["ValorServicos", "ValorIss", "ValorPis", "ValorCofins", "ValorInss", "ValorIr", "ValorCsll"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct Totals {
+    pub(crate) notas: usize,
+    pub(crate) valor_servicos: Decimal,
+    pub(crate) valor_iss: Decimal,
+    pub(crate) valor_pis: Decimal,
+    pub(crate) valor_cofins: Decimal,
+    pub(crate) valor_inss: Decimal,
+    pub(crate) valor_ir: Decimal,
+    pub(crate) valor_csll: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SummaryRow {
+    pub(crate) key: String,
+    // Razão social do tomador; vazio nos outros agrupamentos
+    pub(crate) name: Option<String>,
+    #[serde(flatten)]
+    pub(crate) totals: Totals,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct Summary {
+    pub(crate) total: Totals,
+    pub(crate) months: Vec<SummaryRow>,
+    pub(crate) tomadores: Vec<SummaryRow>,
+    pub(crate) items: Vec<SummaryRow>,
+}
+
+// Valor como escrito na nota ("1000.00", 1000.5); vazio ou inválido conta como zero
+fn decimal_at(nota: &Value, campo: &str) -> Decimal {
+    text_at(nota, &format!("Servico/Valores/{}", campo))
+        .and_then(|texto| Decimal::from_str(texto.trim()).or_else(|_| Decimal::from_scientific(texto.trim())).ok())
+        .unwrap_or_default()
+}
+
+impl Totals {
+    fn add(&mut self, nota: &Value) {
+        self.notas += 1;
+        let somas = [
+            &mut self.valor_servicos,
+            &mut self.valor_iss,
+            &mut self.valor_pis,
+            &mut self.valor_cofins,
+            &mut self.valor_inss,
+            &mut self.valor_ir,
+            &mut self.valor_csll,
+        ];
+        for (soma, campo) in somas.into_iter().zip(VALORES) {
+            *soma += decimal_at(nota, campo);
+        }
+    }
+
+    fn values(&self) -> [Decimal; 7] {
+        [self.valor_servicos, self.valor_iss, self.valor_pis, self.valor_cofins, self.valor_inss, self.valor_ir, self.valor_csll]
+    }
+}
+
+// Mês da competência; sem ela, o da emissão
+fn month(nota: &Value) -> String {
+    ["Competencia", "DataEmissao"]
+        .iter()
+        .find_map(|campo| text_at(nota, campo).and_then(|texto| dates::parse(&texto)))
+        .map(|data| data.local.format("%Y-%m").to_string())
+        .unwrap_or_else(|| "sem competência".to_string())
+}
+
+fn group(grupos: &mut BTreeMap<String, SummaryRow>, key: String, name: Option<String>, nota: &Value) {
+    grupos
+        .entry(key.clone())
+        .or_insert_with(|| SummaryRow { key, name, totals: Totals::default() })
+        .totals
+        .add(nota);
+}
+
+impl Summary {
+    // Canceladas e substituídas (mantidas na saída pela política "flag") ficam fora dos totais
+    pub(crate) fn from_notas(notas: &[Value]) -> Summary {
+        let mut summary = Summary::default();
+        let (mut meses, mut tomadores, mut itens) = (BTreeMap::new(), BTreeMap::new(), BTreeMap::new());
+
+        for nota in notas {
+            if nota.get("NfseCancelamento").is_some() || nota.get("NfseSubstituicao").is_some() {
+                continue;
+            }
+            summary.total.add(nota);
+            group(&mut meses, month(nota), None, nota);
+            let tomador = text_at(nota, "TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj").unwrap_or_default();
+            let razao_social = text_at(nota, "TomadorServico/RazaoSocial").filter(|nome| !nome.is_empty());
+            group(&mut tomadores, tomador, razao_social, nota);
+            group(&mut itens, text_at(nota, "Servico/ItemListaServico").unwrap_or_default(), None, nota);
+        }
+
+        summary.months = meses.into_values().collect();
+        summary.tomadores = tomadores.into_values().collect();
+        summary.items = itens.into_values().collect();
+        summary
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    // Uma linha por grupo, com vírgula decimal como o Excel em português espera
+    pub(crate) fn to_csv(&self) -> String {
+        let mut linhas = vec!["Agrupamento;Chave;Nome;Notas;ValorServicos;ValorIss;ValorPis;ValorCofins;ValorInss;ValorIr;ValorCsll".to_string()];
+        let mut linha = |agrupamento: &str, chave: &str, nome: &str, totals: &Totals| {
+            let mut campos = vec![agrupamento.to_string(), chave.to_string(), nome.to_string(), totals.notas.to_string()];
+            campos.extend(totals.values().into_iter().map(|mut valor| {
+                valor.rescale(2);
+                valor.to_string().replace('.', ",")
+            }));
+            linhas.push(campos.iter().map(|campo| csv_field(campo)).collect::<Vec<_>>().join(";"));
+        };
+
+        linha("total", "", "", &self.total);
+        for (agrupamento, grupos) in [("mês", &self.months), ("tomador", &self.tomadores), ("item", &self.items)] {
+            for grupo in grupos {
+                linha(agrupamento, &grupo.key, grupo.name.as_deref().unwrap_or(""), &grupo.totals);
+            }
+        }
+        linhas.join("\r\n") + "\r\n"
+    }
+
+    // Gravar como <nome da saída>-resumo.<ext> na mesma pasta
+    pub(crate) fn save(&self, output_path: &Path, format: ReportFormat) -> Result<PathBuf, String> {
+        match format {
+            ReportFormat::Json => write_sibling(output_path, "resumo", "json", self.to_json()?),
+            ReportFormat::Csv => write_sibling(output_path, "resumo", "csv", self.to_csv()),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// Conferência aritmética do grupo Servico/Valores: base de cálculo, ISS e valor líquido.
+// As divergências não impedem a conversão; ficam marcadas no relatório da nota.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::nota::number_at;
+use crate::report::NotaReport;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AliquotaFormat {
+    // Abaixo de 1 é fração (0.05), senão percentual (5.00)
+    Auto,
+    Fraction,
+    Percent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TaxSettings {
+    // Diferença máxima aceita, em reais (arredondamento do provedor)
+    pub(crate) tolerance: f64,
+    pub(crate) aliquota: AliquotaFormat,
+}
+
+impl Default for TaxSettings {
+    fn default() -> Self {
+        TaxSettings { tolerance: 0.01, aliquota: AliquotaFormat::Auto }
+    }
+}
+
+fn valor(nota: &Value, campo: &str) -> f64 {
+    number_at(nota, &format!("Servico/Valores/{}", campo)).unwrap_or(0.0)
+}
+
+impl TaxSettings {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if !self.tolerance.is_finite() || self.tolerance < 0.0 {
+            return Err(format!("Tolerância inválida: {}", self.tolerance));
+        }
+        Ok(())
+    }
+
+    // Alíquota como fração (0.05 = 5%)
+    fn aliquota_fraction(&self, aliquota: f64) -> f64 {
+        match self.aliquota {
+            AliquotaFormat::Fraction => aliquota,
+            AliquotaFormat::Percent => aliquota / 100.0,
+            AliquotaFormat::Auto if aliquota < 1.0 => aliquota,
+            AliquotaFormat::Auto => aliquota / 100.0,
+        }
+    }
+
+    fn check(&self, report: &mut NotaReport, campo: &str, esperado: f64, informado: f64) {
+        // Zero equivale a não informado: muitos provedores omitem o campo (ex.: Simples Nacional)
+        if informado == 0.0 {
+            return;
+        }
+        if (esperado - informado).abs() > self.tolerance + 1e-9 {
+            report.inconsistent(campo, esperado, informado);
+        }
+    }
+
+    // Regras (ABRASF):
+    //   BaseCalculo = ValorServicos - ValorDeducoes - DescontoIncondicionado
+    //   ValorIss = BaseCalculo x Aliquota
+    //   ValorLiquidoNfse = ValorServicos - retenções federais - OutrasRetencoes
+    //                      - ISS retido - DescontoIncondicionado - DescontoCondicionado
+    pub(crate) fn check_nota(&self, nota: &Value, report: &mut NotaReport) {
+        let servicos = valor(nota, "ValorServicos");
+        let desconto_incondicionado = valor(nota, "DescontoIncondicionado");
+
+        let base = servicos - valor(nota, "ValorDeducoes") - desconto_incondicionado;
+        self.check(report, "Servico/Valores/BaseCalculo", base, valor(nota, "BaseCalculo"));
+
+        let aliquota = valor(nota, "Aliquota");
+        if aliquota > 0.0 {
+            let base_informada = valor(nota, "BaseCalculo");
+            let base_iss = if base_informada == 0.0 { base } else { base_informada };
+            let iss = base_iss * self.aliquota_fraction(aliquota);
+            self.check(report, "Servico/Valores/ValorIss", iss, valor(nota, "ValorIss"));
+        }
+
+        let retencoes: f64 = ["ValorPis", "ValorCofins", "ValorInss", "ValorIr", "ValorCsll", "OutrasRetencoes"]
+            .iter()
+            .map(|campo| valor(nota, campo))
+            .sum();
+        // IssRetido: 1 = sim, 2 = não
+        let iss_retido = if valor(nota, "IssRetido") == 1.0 { valor(nota, "ValorIss") } else { 0.0 };
+        let liquido = servicos - retencoes - iss_retido - desconto_incondicionado - valor(nota, "DescontoCondicionado");
+        self.check(report, "Servico/Valores/ValorLiquidoNfse", liquido, valor(nota, "ValorLiquidoNfse"));
+    }
+}
@@ -0,0 +1,130 @@
+// Conversão inversa: notas do modelo interno -> ConsultarNfseResposta do ABRASF,
+// com cada nota embrulhada em CompNfse -> Nfse -> InfNfse.
+use serde_json::{json, Map, Value};
+
+use super::prune_empty;
+use crate::build_xml_from_json;
+use crate::nota::ABRASF_NAMESPACE;
+
+pub(super) fn render(notas: &[Value]) -> String {
+    let comp_nfse: Vec<Value> = notas
+        .iter()
+        .map(to_comp_nfse)
+        .collect();
+
+    build_xml_from_json(&json!({
+        "ConsultarNfseResposta": {
+            "@_xmlns": ABRASF_NAMESPACE,
+            "ListaNfse": { "CompNfse": comp_nfse }
+        }
+    }))
+}
+
+// Cancelamento e substituição ficam no CompNfse, ao lado da Nfse
+fn to_comp_nfse(nota: &Value) -> Value {
+    let mut comp = Map::new();
+    comp.insert("Nfse".to_string(), json!({ "InfNfse": to_inf_nfse(nota) }));
+    for campo in ["NfseCancelamento", "NfseSubstituicao"] {
+        if let Some(valor) = nota.get(campo) {
+            comp.insert(campo.to_string(), valor.clone());
+        }
+    }
+    Value::Object(comp)
+}
+
+fn to_inf_nfse(nota: &Value) -> Value {
+    let Value::Object(campos) = nota else {
+        return Value::Null;
+    };
+
+    let inf: Map<String, Value> = campos
+        .iter()
+        .filter_map(|(key, value)| match key.as_str() {
+            // Numero sem o preenchimento com zeros feito na ida
+            "Numero" => {
+                let numero = value.as_str().unwrap_or_default().trim_start_matches('0');
+                Some((key.clone(), json!(if numero.is_empty() { "0" } else { numero })))
+            }
+            // Grafia herdada do conversor.js; o ABRASF usa ConstrucaoCivil
+            "ContrucaoCivil" => Some(("ConstrucaoCivil".to_string(), value.clone())),
+            "NfseCancelamento" | "NfseSubstituicao" => None,
+            _ => Some((key.clone(), value.clone())),
+        })
+        .collect();
+
+    prune_empty(Value::Object(inf))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::company::CompanyProfile;
+    use crate::extract_notas;
+    use crate::writers::{render, CsvSettings, JsonSettings, OutputFormat};
+
+    const CONSULTAR_NFSE_RESPOSTA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ConsultarNfseResposta xmlns="http://www.abrasf.org.br/nfse.xsd">
+  <ListaNfse>
+    <CompNfse>
+      <Nfse>
+        <InfNfse Id="nfse1">
+          <Numero>123</Numero>
+          <CodigoVerificacao>ABC123</CodigoVerificacao>
+          <DataEmissao>2025-05-10T14:22:01</DataEmissao>
+          <NaturezaOperacao>1</NaturezaOperacao>
+          <OptanteSimplesNacional>2</OptanteSimplesNacional>
+          <Competencia>202505Z</Competencia>
+          <Servico>
+            <Valores>
+              <ValorServicos>1000.00</ValorServicos>
+              <ValorPis>6.50</ValorPis>
+              <IssRetido>2</IssRetido>
+              <ValorIss>50.00</ValorIss>
+              <BaseCalculo>1000.00</BaseCalculo>
+              <Aliquota>0.05</Aliquota>
+              <ValorLiquidoNfse>993.50</ValorLiquidoNfse>
+            </Valores>
+            <ItemListaServico>1005</ItemListaServico>
+            <Discriminacao>Suporte &amp; manutenção</Discriminacao>
+            <CodigoMunicipio>2611606</CodigoMunicipio>
+          </Servico>
+          <PrestadorServico>
+            <IdentificacaoPrestador><Cnpj>11222333000181</Cnpj><InscricaoMunicipal>12345</InscricaoMunicipal></IdentificacaoPrestador>
+            <RazaoSocial>Prestador LTDA</RazaoSocial>
+            <Endereco><Endereco>Rua A</Endereco><Uf>PE</Uf></Endereco>
+          </PrestadorServico>
+          <TomadorServico>
+            <IdentificacaoTomador><CpfCnpj><Cnpj>12345678000195</Cnpj></CpfCnpj></IdentificacaoTomador>
+            <RazaoSocial>Tomador SA</RazaoSocial>
+          </TomadorServico>
+          <OrgaoGerador><CodigoMunicipio>2611606</CodigoMunicipio><Uf>PE</Uf></OrgaoGerador>
+        </InfNfse>
+      </Nfse>
+    </CompNfse>
+    <CompNfse>
+      <Nfse>
+        <InfNfse Id="nfse2">
+          <Numero>124</Numero>
+          <Servico><Valores><ValorServicos>200</ValorServicos></Valores></Servico>
+        </InfNfse>
+      </Nfse>
+    </CompNfse>
+  </ListaNfse>
+</ConsultarNfseResposta>"#;
+
+    #[test]
+    fn round_trip_preserves_mapped_fields() {
+        let ida = extract_notas(CONSULTAR_NFSE_RESPOSTA, None, &CompanyProfile::default(), false).unwrap().notas;
+        let lista_nota_fiscal = render(OutputFormat::ListaNotaFiscal, ida.clone(), &CsvSettings::default(), &JsonSettings::default()).unwrap();
+        let lista_nota_fiscal = String::from_utf8(lista_nota_fiscal[0].content.clone()).unwrap();
+
+        // ListaNotaFiscal -> ConsultarNfseResposta -> notas novamente
+        let lidas = extract_notas(&lista_nota_fiscal, None, &CompanyProfile::default(), false).unwrap().notas;
+        let consultar = super::render(&lidas);
+        assert!(consultar.contains("<CompNfse>"));
+        assert!(consultar.contains("<Numero>123</Numero>"));
+
+        let volta = extract_notas(&consultar, None, &CompanyProfile::default(), false).unwrap().notas;
+        assert_eq!(ida, lidas);
+        assert_eq!(ida, volta);
+    }
+}
@@ -0,0 +1,78 @@
+// Saída em JSON: a árvore do XML como sai do parse_xml_to_json e as notas do modelo interno,
+// em JSON ou JSON Lines (uma nota por linha, para o pipeline de dados). O prefixo dos
+// atributos, a chave do texto e os elementos que sempre viram lista seguem a configuração.
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct JsonSettings {
+    // Indentado ou numa linha só (JSON Lines é sempre compacto)
+    pub(crate) pretty: bool,
+    // Prefixo das chaves que vêm de atributos ("@_" no parser)
+    pub(crate) attribute_prefix: String,
+    // Chave do texto de um elemento que também tem atributos ("#text" no XML gerado)
+    pub(crate) text_key: String,
+    // Elementos que saem como lista mesmo com uma ocorrência só (ex.: "CompNfse")
+    pub(crate) arrays: Vec<String>,
+}
+
+impl Default for JsonSettings {
+    fn default() -> Self {
+        JsonSettings { pretty: true, attribute_prefix: "@_".to_string(), text_key: "#text".to_string(), arrays: Vec::new() }
+    }
+}
+
+impl JsonSettings {
+    // Sem prefixo, um atributo e um elemento com o mesmo nome iriam para a mesma chave
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.attribute_prefix.is_empty() {
+            return Err("O prefixo dos atributos no JSON não pode ser vazio".to_string());
+        }
+        if self.text_key.is_empty() || self.text_key.starts_with(&self.attribute_prefix) {
+            return Err(format!("Chave de texto inválida no JSON: {:?}", self.text_key));
+        }
+        Ok(())
+    }
+
+    // Aplicar as convenções sobre a árvore gerada com "@_" e listas só para repetidos
+    pub(crate) fn apply(&self, value: Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(chave, valor)| {
+                        let valor = match self.apply(valor) {
+                            Value::Array(lista) => Value::Array(lista),
+                            valor if self.arrays.contains(&chave) => Value::Array(vec![valor]),
+                            valor => valor,
+                        };
+                        let chave = match chave.strip_prefix("@_") {
+                            Some(nome) => format!("{}{}", self.attribute_prefix, nome),
+                            None if chave == "#text" => self.text_key.clone(),
+                            None => chave,
+                        };
+                        (chave, valor)
+                    })
+                    .collect::<Map<String, Value>>(),
+            ),
+            Value::Array(lista) => Value::Array(lista.into_iter().map(|item| self.apply(item)).collect()),
+            other => other,
+        }
+    }
+
+    pub(crate) fn to_string(&self, value: &Value) -> Result<String, String> {
+        let texto = if self.pretty { serde_json::to_string_pretty(value) } else { serde_json::to_string(value) };
+        texto.map(|texto| texto + "\n").map_err(|e| e.to_string())
+    }
+}
+
+pub(crate) fn render_json(notas: Vec<Value>, settings: &JsonSettings) -> Result<String, String> {
+    settings.to_string(&settings.apply(Value::Array(notas)))
+}
+
+pub(crate) fn render_json_lines(notas: Vec<Value>, settings: &JsonSettings) -> Result<String, String> {
+    notas
+        .into_iter()
+        .map(|nota| serde_json::to_string(&settings.apply(nota)).map(|linha| linha + "\n").map_err(|e| e.to_string()))
+        .collect()
+}
@@ -0,0 +1,168 @@
+// Formatos de saída: cada escritor transforma as notas do modelo interno (nota.rs)
+// em um ou mais documentos prontos para gravar.
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::providers::Record;
+use crate::report::NotaReport;
+use crate::{build_xml_from_json, nota};
+
+mod consultar_nfse;
+mod json;
+mod nacional;
+mod table;
+
+pub(crate) use json::JsonSettings;
+pub(crate) use table::CsvSettings;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OutputFormat {
+    // ListaNotaFiscal no padrão ABRASF (formato original do conversor)
+    #[default]
+    ListaNotaFiscal,
+    // NFSe do Padrão Nacional, um documento por nota
+    Nacional,
+    // ConsultarNfseResposta do ABRASF (conversão inversa da ListaNotaFiscal)
+    ConsultarNfseResposta,
+    // Planilha com uma linha por nota
+    Csv,
+    Xlsx,
+    // Notas do modelo interno como lista JSON ou uma por linha (JSON Lines)
+    Json,
+    JsonLines,
+}
+
+impl OutputFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::ListaNotaFiscal | OutputFormat::Nacional | OutputFormat::ConsultarNfseResposta => "xml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+        }
+    }
+
+    pub(crate) fn filter_name(self) -> &'static str {
+        match self {
+            OutputFormat::ListaNotaFiscal | OutputFormat::Nacional | OutputFormat::ConsultarNfseResposta => "XML Files",
+            OutputFormat::Csv => "CSV Files",
+            OutputFormat::Xlsx => "Excel Files",
+            OutputFormat::Json => "JSON Files",
+            OutputFormat::JsonLines => "JSON Lines Files",
+        }
+    }
+
+    // Formatos que só fazem sentido para notas (não para a reformatação genérica de XML)
+    pub(crate) fn is_nota_only(self) -> bool {
+        matches!(self, OutputFormat::Csv | OutputFormat::Xlsx | OutputFormat::Json | OutputFormat::JsonLines)
+    }
+}
+
+// Documento gerado; `suffix` distingue os arquivos quando o formato gera mais de um
+pub(crate) struct OutputDocument {
+    pub(crate) suffix: Option<String>,
+    pub(crate) content: Vec<u8>,
+}
+
+pub(crate) fn render(format: OutputFormat, notas: Vec<Value>, csv: &CsvSettings, json: &JsonSettings) -> Result<Vec<OutputDocument>, String> {
+    match format {
+        OutputFormat::ListaNotaFiscal => Ok(vec![OutputDocument {
+            suffix: None,
+            content: build_xml_from_json(&nota::lista_nota_fiscal(notas)).into_bytes(),
+        }]),
+        OutputFormat::Nacional => {
+            if notas.is_empty() {
+                return Err("Nenhuma nota para exportar".to_string());
+            }
+            nacional::render(&notas)
+        }
+        OutputFormat::ConsultarNfseResposta => Ok(vec![OutputDocument {
+            suffix: None,
+            content: consultar_nfse::render(&notas).into_bytes(),
+        }]),
+        OutputFormat::Csv => Ok(vec![OutputDocument { suffix: None, content: table::render_csv(&notas, csv).into_bytes() }]),
+        OutputFormat::Xlsx => Ok(vec![OutputDocument { suffix: None, content: table::render_xlsx(&notas)? }]),
+        OutputFormat::Json => Ok(vec![OutputDocument { suffix: None, content: json::render_json(notas, json)?.into_bytes() }]),
+        OutputFormat::JsonLines => Ok(vec![OutputDocument { suffix: None, content: json::render_json_lines(notas, json)?.into_bytes() }]),
+    }
+}
+
+// Avisos do formato de saída, registrados no relatório de cada nota antes de gravar
+pub(crate) fn check(format: OutputFormat, notas: &[Value], reports: &mut [NotaReport]) {
+    if format == OutputFormat::Nacional {
+        for (nota, report) in notas.iter().zip(reports) {
+            nacional::check(nota, report);
+        }
+    }
+}
+
+// Campos opcionais vazios não podem aparecer nos layouts com schema estrito
+fn prune_empty(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, prune_empty(value)))
+                .filter(|(_, value)| match value {
+                    Value::Null => false,
+                    Value::String(s) => !s.is_empty(),
+                    Value::Object(map) => !map.is_empty(),
+                    _ => true,
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        other => other,
+    }
+}
+
+// Gravar os documentos: um único documento vai para `save_path`; vários recebem o sufixo
+// no nome do arquivo (ex.: notas-00000123.xml)
+pub(crate) fn save(save_path: &Path, documentos: Vec<OutputDocument>) -> Result<Vec<PathBuf>, String> {
+    let unico = documentos.len() == 1;
+    let mut paths = Vec::new();
+
+    for documento in documentos {
+        let path = match documento.suffix {
+            Some(suffix) if !unico => suffixed_path(save_path, &suffix),
+            _ => save_path.to_path_buf(),
+        };
+        fs::write(&path, &documento.content).map_err(|e| e.to_string())?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+pub(crate) fn suffixed_path(save_path: &Path, suffix: &str) -> PathBuf {
+    let stem = save_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let file_name = match save_path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, suffix),
+    };
+    save_path.with_file_name(file_name)
+}
+
+// Quarentena do modo parcial: os elementos originais das notas rejeitadas (CompNfse, NFe...)
+// num XML à parte, ao lado da saída, para correção e reenvio
+pub(crate) fn save_quarantine(save_path: &Path, rejected: &[Record]) -> Result<Option<PathBuf>, String> {
+    if rejected.is_empty() {
+        return Ok(None);
+    }
+
+    let mut elementos: Map<String, Value> = Map::new();
+    for record in rejected {
+        if let Value::Array(lista) = elementos
+            .entry(record.element.clone())
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            lista.push(record.source.clone());
+        }
+    }
+
+    let path = suffixed_path(&save_path.with_extension("xml"), "quarentena");
+    let xml = build_xml_from_json(&json!({ "Quarentena": elementos }));
+    fs::write(&path, xml).map_err(|e| e.to_string())?;
+    Ok(Some(path))
+}
@@ -0,0 +1,396 @@
+// Exportação para o Padrão Nacional (NFSe versão 1.00): cada nota do modelo interno
+// vira um documento NFSe -> infNFSe com a DPS correspondente.
+use serde_json::{json, Map, Value};
+
+use super::{prune_empty, OutputDocument};
+use crate::build_xml_from_json;
+use crate::dates::{self, DateSettings};
+use crate::municipalities;
+use crate::nota::{number_at, str_or, text_at};
+use crate::providers::nacional::NACIONAL_NAMESPACE;
+use crate::report::NotaReport;
+use crate::services::{self, ServiceCode};
+
+const VERSAO_LEIAUTE: &str = "1.00";
+const VER_APLIC: &str = concat!("xmlconverter-", env!("CARGO_PKG_VERSION"));
+
+pub(super) fn render(notas: &[Value]) -> Result<Vec<OutputDocument>, String> {
+    notas
+        .iter()
+        .map(|nota| {
+            let numero = str_or(nota, "Numero", "00000000");
+            let nfse = to_nfse(nota).map_err(|erro| format!("Nota {}: {}", numero, erro))?;
+            Ok(OutputDocument { suffix: Some(numero), content: build_xml_from_json(&nfse).into_bytes() })
+        })
+        .collect()
+}
+
+// Nomes e descrições que as tabelas embutidas não têm saem omitidos da NFSe, com aviso
+pub(super) fn check(nota: &Value, report: &mut NotaReport) {
+    if let Ok(codigo) = c_trib_nac(&str_or(nota, "Servico/ItemListaServico", "")) {
+        if services::national_description(&codigo).is_none() {
+            report.warn(format!("cTribNac {} sem descrição na tabela nacional; a NFSe sai sem xTribNac", codigo));
+        }
+    }
+    let emissor = str_or(nota, "OrgaoGerador/CodigoMunicipio", "");
+    let servico = str_or(nota, "Servico/CodigoMunicipio", "");
+    let mut sem_nome: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (codigo, campo) in [(emissor.as_str(), "xLocEmi"), (servico.as_str(), "xLocPrestacao"), (servico.as_str(), "xLocIncid")] {
+        if codigo.is_empty() || municipalities::name_of(codigo).is_some() {
+            continue;
+        }
+        match sem_nome.iter_mut().find(|(outro, _)| *outro == codigo) {
+            Some((_, campos)) => campos.push(campo),
+            None => sem_nome.push((codigo, vec![campo])),
+        }
+    }
+    for (codigo, campos) in sem_nome {
+        report.warn(format!("Município {} não consta da tabela do IBGE; a NFSe sai sem {}", codigo, campos.join(", ")));
+    }
+}
+
+fn digits(text: &str) -> String {
+    text.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+fn decimal(nota: &Value, campo: &str) -> String {
+    format!("{:.2}", number_at(nota, &format!("Servico/Valores/{}", campo)).unwrap_or(0.0))
+}
+
+// ItemListaServico ("1005", "10.05", "100501") -> cTribNac
+fn c_trib_nac(item_lista_servico: &str) -> Result<String, String> {
+    match ServiceCode::parse(item_lista_servico) {
+        Some(codigo) if codigo.exists() => Ok(codigo.national()),
+        _ => Err(format!("ItemListaServico {:?} não consta da LC 116/2003", item_lista_servico)),
+    }
+}
+
+// Alíquota no Padrão Nacional é percentual (5.00); o ABRASF costuma trazer fração (0.05)
+fn aliquota_percentual(nota: &Value) -> String {
+    let aliquota = number_at(nota, "Servico/Valores/Aliquota").unwrap_or(0.0);
+    let percentual = if aliquota > 0.0 && aliquota < 1.0 { aliquota * 100.0 } else { aliquota };
+    format!("{:.2}", percentual)
+}
+
+// IssRetido ABRASF (1 = sim) -> tpRetISSQN (1 = não retido, 2 = retido pelo tomador)
+fn tp_ret_issqn(nota: &Value) -> &'static str {
+    if text_at(nota, "Servico/Valores/IssRetido").as_deref() == Some("1") {
+        "2"
+    } else {
+        "1"
+    }
+}
+
+// OptanteSimplesNacional ABRASF (1 = sim) -> opSimpNac (1 = não optante, 3 = ME/EPP)
+fn op_simp_nac(nota: &Value) -> &'static str {
+    if text_at(nota, "OptanteSimplesNacional").as_deref() == Some("1") {
+        "3"
+    } else {
+        "1"
+    }
+}
+
+// CNPJ tem 14 dígitos; abaixo disso o documento é um CPF
+fn documento(numero: &str) -> Map<String, Value> {
+    let numero = digits(numero);
+    let mut map = Map::new();
+    match numero.len() {
+        0 => {}
+        1..=11 => {
+            map.insert("CPF".to_string(), json!(format!("{:0>11}", numero)));
+        }
+        _ => {
+            map.insert("CNPJ".to_string(), json!(format!("{:0>14}", numero)));
+        }
+    }
+    map
+}
+
+// Tipo de inscrição (1 = CPF, 2 = CNPJ) e número do prestador, usados na chave e no Id da DPS
+fn inscricao_prestador(nota: &Value) -> (&'static str, String) {
+    let inscricao = digits(&str_or(nota, "PrestadorServico/IdentificacaoPrestador/Cnpj", ""));
+    let tp_insc = if inscricao.len() == 11 { "1" } else { "2" };
+    (tp_insc, inscricao)
+}
+
+fn date_part(data: &str) -> String {
+    data.chars().take(10).collect()
+}
+
+// dhEmi e dhProc exigem o fuso (TSDateTimeUTC); sem ele na nota, vale o horário de Brasília
+fn data_hora_com_fuso(data: &str) -> String {
+    let formato = DateSettings { format: "%Y-%m-%dT%H:%M:%S%:z".to_string(), ..DateSettings::default() };
+    dates::parse(data)
+        .and_then(|parsed| formato.format_date(parsed, false).ok())
+        .unwrap_or_else(|| data.to_string())
+}
+
+// Chave de acesso de 50 posições: cMun + ambGer + tpInsc + nInsc + nNFSe + AAMM + cNum + DV.
+// Notas que vieram do Padrão Nacional já trazem a chave original no CodigoVerificacao. Cada
+// parte precisa caber no seu tamanho; a nota que não cabe no leiaute é rejeitada.
+fn chave_acesso(nota: &Value) -> Result<String, String> {
+    let codigo_verificacao = str_or(nota, "CodigoVerificacao", "");
+    if codigo_verificacao.len() == 50 && codigo_verificacao.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(codigo_verificacao);
+    }
+
+    let municipio = str_or(nota, "OrgaoGerador/CodigoMunicipio", "");
+    if municipio.len() != 7 || !municipio.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("código do município emissor {:?} não tem 7 dígitos", municipio));
+    }
+    let (tp_insc, inscricao) = inscricao_prestador(nota);
+    if inscricao.is_empty() || inscricao.len() > 14 {
+        return Err(format!("CNPJ/CPF do prestador {:?} não cabe nas 14 posições da chave", inscricao));
+    }
+    let numero = str_or(nota, "Numero", "");
+    let numero_chave = numero.trim().trim_start_matches('0');
+    if numero.trim().is_empty() || !numero_chave.chars().all(|c| c.is_ascii_digit()) || numero_chave.len() > 13 {
+        return Err(format!("Numero {:?} não cabe nas 13 posições numéricas da chave", numero));
+    }
+    let data = digits(&str_or(nota, "DataEmissao", ""));
+    let aamm: String = data.chars().skip(2).take(4).collect();
+    if aamm.len() != 4 {
+        return Err("DataEmissao ausente ou inválida para o AAMM da chave".to_string());
+    }
+    // cNum: últimos 9 dígitos do código de verificação de origem
+    let codigo = digits(&codigo_verificacao);
+    let codigo_numerico = &codigo[codigo.len().saturating_sub(9)..];
+
+    let base = format!("{}2{}{:0>14}{:0>13}{}{:0>9}", municipio, tp_insc, inscricao, numero_chave, aamm, codigo_numerico);
+    Ok(format!("{}{}", base, digito_modulo_11(&base)))
+}
+
+fn digito_modulo_11(base: &str) -> u32 {
+    let soma: u32 = base
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .zip((2..=9).cycle())
+        .map(|(digito, peso)| digito * peso)
+        .sum();
+    match 11 - soma % 11 {
+        10 | 11 => 0,
+        dv => dv,
+    }
+}
+
+fn to_nfse(nota: &Value) -> Result<Value, String> {
+    let c_trib_nac = c_trib_nac(&str_or(nota, "Servico/ItemListaServico", ""))?;
+    let x_trib_nac = services::national_description(&c_trib_nac);
+    let chave = chave_acesso(nota)?;
+    let numero = str_or(nota, "Numero", "").trim_start_matches('0').to_string();
+    let data_emissao = data_hora_com_fuso(&str_or(nota, "DataEmissao", ""));
+    let municipio_emissor = str_or(nota, "OrgaoGerador/CodigoMunicipio", "");
+    // O Padrão Nacional separa local da prestação e de incidência; o ABRASF só tem o do serviço
+    let municipio_servico = str_or(nota, "Servico/CodigoMunicipio", "");
+    let x_loc_emi = municipalities::name_of(&municipio_emissor);
+    let x_loc_servico = municipalities::name_of(&municipio_servico);
+
+    // Id da DPS: cMun + tpInsc + nInsc + série + nDPS
+    let (tp_insc, inscricao) = inscricao_prestador(nota);
+    let id_dps = format!("DPS{:0>7}{}{:0>14}{:0>5}{:0>15}", digits(&municipio_emissor), tp_insc, inscricao, "1", numero);
+
+    let retencoes_federais: f64 = ["ValorPis", "ValorCofins", "ValorInss", "ValorIr", "ValorCsll"]
+        .iter()
+        .map(|campo| number_at(nota, &format!("Servico/Valores/{}", campo)).unwrap_or(0.0))
+        .sum();
+    let iss_retido = if tp_ret_issqn(nota) == "2" {
+        number_at(nota, "Servico/Valores/ValorIss").unwrap_or(0.0)
+    } else {
+        0.0
+    };
+
+    let mut emit = documento(&str_or(nota, "PrestadorServico/IdentificacaoPrestador/Cnpj", ""));
+    emit.insert("IM".to_string(), json!(str_or(nota, "PrestadorServico/IdentificacaoPrestador/InscricaoMunicipal", "")));
+    emit.insert("xNome".to_string(), json!(str_or(nota, "PrestadorServico/RazaoSocial", "")));
+    emit.insert("enderNac".to_string(), json!({
+        "xLgr": str_or(nota, "PrestadorServico/Endereco/Endereco", ""),
+        "nro": str_or(nota, "PrestadorServico/Endereco/Numero", ""),
+        "xBairro": str_or(nota, "PrestadorServico/Endereco/Bairro", ""),
+        "cMun": str_or(nota, "PrestadorServico/Endereco/CodigoMunicipio", ""),
+        "UF": str_or(nota, "PrestadorServico/Endereco/Uf", ""),
+        "CEP": digits(&str_or(nota, "PrestadorServico/Endereco/Cep", ""))
+    }));
+    emit.insert("fone".to_string(), json!(digits(&str_or(nota, "PrestadorServico/Contato/Telefone", ""))));
+    emit.insert("email".to_string(), json!(str_or(nota, "PrestadorServico/Contato/Email", "")));
+
+    let mut prest = documento(&str_or(nota, "PrestadorServico/IdentificacaoPrestador/Cnpj", ""));
+    prest.insert("IM".to_string(), json!(str_or(nota, "PrestadorServico/IdentificacaoPrestador/InscricaoMunicipal", "")));
+    prest.insert("regTrib".to_string(), json!({ "opSimpNac": op_simp_nac(nota), "regEspTrib": "0" }));
+
+    let mut toma = documento(&str_or(nota, "TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj", ""));
+    toma.insert("IM".to_string(), json!(str_or(nota, "TomadorServico/IdentificacaoTomador/InscricaoMunicipal", "")));
+    toma.insert("xNome".to_string(), json!(str_or(nota, "TomadorServico/RazaoSocial", "")));
+    toma.insert("end".to_string(), json!({
+        "endNac": {
+            "cMun": str_or(nota, "TomadorServico/Endereco/CodigoMunicipio", ""),
+            "CEP": digits(&str_or(nota, "TomadorServico/Endereco/Cep", ""))
+        },
+        "xLgr": str_or(nota, "TomadorServico/Endereco/Endereco", ""),
+        "nro": str_or(nota, "TomadorServico/Endereco/Numero", ""),
+        "xCpl": str_or(nota, "TomadorServico/Endereco/Complemento", ""),
+        "xBairro": str_or(nota, "TomadorServico/Endereco/Bairro", "")
+    }));
+    toma.insert("fone".to_string(), json!(digits(&str_or(nota, "TomadorServico/Contato/Telefone", ""))));
+    toma.insert("email".to_string(), json!(str_or(nota, "TomadorServico/Contato/Email", "")));
+
+    let valores_dps = json!({
+        "vServPrest": { "vServ": decimal(nota, "ValorServicos") },
+        "vDescCondIncond": {
+            "vDescIncond": decimal(nota, "DescontoIncondicionado"),
+            "vDescCond": decimal(nota, "DescontoCondicionado")
+        },
+        "vDedRed": { "vDR": decimal(nota, "ValorDeducoes") },
+        "trib": {
+            "tribMun": { "tribISSQN": "1", "tpRetISSQN": tp_ret_issqn(nota) },
+            "tribFed": {
+                "piscofins": {
+                    "CST": "00",
+                    "vPis": decimal(nota, "ValorPis"),
+                    "vCofins": decimal(nota, "ValorCofins")
+                },
+                "vRetCP": decimal(nota, "ValorInss"),
+                "vRetIRRF": decimal(nota, "ValorIr"),
+                "vRetCSLL": decimal(nota, "ValorCsll")
+            },
+            "totTrib": { "indTotTrib": "0" }
+        }
+    });
+
+    let nfse = json!({
+        "@_versao": VERSAO_LEIAUTE,
+        "@_xmlns": NACIONAL_NAMESPACE,
+        "infNFSe": {
+            "@_Id": format!("NFS{}", chave),
+            "xLocEmi": x_loc_emi,
+            "xLocPrestacao": x_loc_servico,
+            "nNFSe": numero,
+            "cLocIncid": municipio_servico,
+            "xLocIncid": x_loc_servico,
+            "xTribNac": x_trib_nac,
+            "verAplic": VER_APLIC,
+            "ambGer": "2",
+            "tpEmis": "1",
+            "procEmi": "1",
+            "cStat": "100",
+            "dhProc": data_emissao,
+            "nDFSe": numero,
+            "emit": emit,
+            "valores": {
+                "vBC": decimal(nota, "BaseCalculo"),
+                "pAliqAplic": aliquota_percentual(nota),
+                "vISSQN": decimal(nota, "ValorIss"),
+                "vTotalRet": format!("{:.2}", retencoes_federais + iss_retido),
+                "vLiq": decimal(nota, "ValorLiquidoNfse")
+            },
+            "DPS": {
+                "@_versao": VERSAO_LEIAUTE,
+                "infDPS": {
+                    "@_Id": id_dps,
+                    "tpAmb": "1",
+                    "dhEmi": data_emissao,
+                    "verAplic": VER_APLIC,
+                    "serie": "1",
+                    "nDPS": numero,
+                    "dCompet": date_part(&str_or(nota, "Competencia", "")),
+                    "tpEmit": "1",
+                    "cLocEmi": municipio_emissor,
+                    "prest": prest,
+                    "toma": toma,
+                    "serv": {
+                        "locPrest": { "cLocPrestacao": municipio_servico },
+                        "cServ": {
+                            "cTribNac": c_trib_nac,
+                            "xDescServ": str_or(nota, "Servico/Discriminacao", "")
+                        }
+                    },
+                    "valores": valores_dps
+                }
+            }
+        }
+    });
+
+    Ok(json!({ "NFSe": prune_empty(nfse) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::company::CompanyProfile;
+    use crate::extract_notas;
+    use crate::report::NotaReport;
+    use crate::writers::{render, CsvSettings, JsonSettings, OutputFormat};
+
+    const CONSULTAR_NFSE_RESPOSTA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ConsultarNfseResposta xmlns="http://www.abrasf.org.br/nfse.xsd">
+  <ListaNfse>
+    <CompNfse>
+      <Nfse>
+        <InfNfse Id="nfse5">
+          <Numero>5</Numero>
+          <CodigoVerificacao>AB12CD34</CodigoVerificacao>
+          <DataEmissao>2025-05-10T14:22:01</DataEmissao>
+          <OptanteSimplesNacional>2</OptanteSimplesNacional>
+          <Competencia>2025-05-01</Competencia>
+          <Servico>
+            <Valores>
+              <ValorServicos>1000.00</ValorServicos>
+              <IssRetido>1</IssRetido>
+              <ValorIss>50.00</ValorIss>
+              <BaseCalculo>1000.00</BaseCalculo>
+              <Aliquota>0.05</Aliquota>
+              <ValorLiquidoNfse>950.00</ValorLiquidoNfse>
+            </Valores>
+            <ItemListaServico>17.01</ItemListaServico>
+            <Discriminacao>Assessoria</Discriminacao>
+            <CodigoMunicipio>3509502</CodigoMunicipio>
+          </Servico>
+          <PrestadorServico>
+            <IdentificacaoPrestador><Cnpj>11222333000181</Cnpj><InscricaoMunicipal>12345</InscricaoMunicipal></IdentificacaoPrestador>
+            <RazaoSocial>Prestador LTDA</RazaoSocial>
+          </PrestadorServico>
+          <TomadorServico>
+            <IdentificacaoTomador><CpfCnpj><Cnpj>12345678000195</Cnpj></CpfCnpj></IdentificacaoTomador>
+            <RazaoSocial>Tomador SA</RazaoSocial>
+          </TomadorServico>
+          <OrgaoGerador><CodigoMunicipio>3509502</CodigoMunicipio><Uf>SP</Uf></OrgaoGerador>
+        </InfNfse>
+      </Nfse>
+    </CompNfse>
+  </ListaNfse>
+</ConsultarNfseResposta>"#;
+
+    fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+        let abertura = format!("<{}>", tag);
+        let inicio = xml.find(&abertura)? + abertura.len();
+        let fim = xml[inicio..].find(&format!("</{}>", tag))?;
+        Some(&xml[inicio..inicio + fim])
+    }
+
+    #[test]
+    fn exports_nota_outside_embedded_tables() {
+        let notas = extract_notas(CONSULTAR_NFSE_RESPOSTA, None, &CompanyProfile::default(), false).unwrap().notas;
+        let documentos = render(OutputFormat::Nacional, notas.clone(), &CsvSettings::default(), &JsonSettings::default()).unwrap();
+        let xml = String::from_utf8(documentos[0].content.clone()).unwrap();
+
+        assert_eq!(element(&xml, "cTribNac"), Some("170101"));
+        assert_eq!(element(&xml, "cLocIncid"), Some("3509502"));
+        assert_eq!(element(&xml, "tpRetISSQN"), Some("2"));
+        assert_eq!(element(&xml, "xLocIncid"), None);
+
+        let inicio = xml.find("Id=\"NFS").unwrap() + "Id=\"NFS".len();
+        let chave = &xml[inicio..inicio + xml[inicio..].find('"').unwrap()];
+        assert_eq!(chave.len(), 50);
+        assert!(chave.starts_with("35095022211222333000181"));
+        assert_eq!(&chave[23..36], "0000000000005");
+
+        let mut report = NotaReport::default();
+        super::check(&notas[0], &mut report);
+        assert_eq!(
+            report.warnings,
+            vec![
+                "cTribNac 170101 sem descrição na tabela nacional; a NFSe sai sem xTribNac".to_string(),
+                "Município 3509502 não consta da tabela do IBGE; a NFSe sai sem xLocEmi, xLocPrestacao, xLocIncid".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// Planilha plana para o financeiro: uma linha por nota com número, datas, prestador, tomador,
+// todos os campos de Valores e os códigos do serviço. CSV e XLSX usam as mesmas colunas.
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Deserialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::nota::text_at;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    // Valor monetário, sempre com duas casas
+    Money,
+    // Alíquota como veio na nota (fração ou percentual)
+    Rate,
+}
+
+const COLUNAS: &[(&str, &str, Kind)] = &[
+    ("Numero", "Numero", Kind::Text),
+    ("CodigoVerificacao", "CodigoVerificacao", Kind::Text),
+    ("DataEmissao", "DataEmissao", Kind::Text),
+    ("Competencia", "Competencia", Kind::Text),
+    ("NaturezaOperacao", "NaturezaOperacao", Kind::Text),
+    ("PrestadorCnpj", "PrestadorServico/IdentificacaoPrestador/Cnpj", Kind::Text),
+    ("PrestadorInscricaoMunicipal", "PrestadorServico/IdentificacaoPrestador/InscricaoMunicipal", Kind::Text),
+    ("PrestadorRazaoSocial", "PrestadorServico/RazaoSocial", Kind::Text),
+    ("TomadorCpfCnpj", "TomadorServico/IdentificacaoTomador/CpfCnpj/Cnpj", Kind::Text),
+    ("TomadorInscricaoMunicipal", "TomadorServico/IdentificacaoTomador/InscricaoMunicipal", Kind::Text),
+    ("TomadorRazaoSocial", "TomadorServico/RazaoSocial", Kind::Text),
+    ("ItemListaServico", "Servico/ItemListaServico", Kind::Text),
+    ("CodigoCnae", "Servico/CodigoCnae", Kind::Text),
+    ("CodigoMunicipio", "Servico/CodigoMunicipio", Kind::Text),
+    ("Discriminacao", "Servico/Discriminacao", Kind::Text),
+    ("ValorServicos", "Servico/Valores/ValorServicos", Kind::Money),
+    ("ValorDeducoes", "Servico/Valores/ValorDeducoes", Kind::Money),
+    ("ValorPis", "Servico/Valores/ValorPis", Kind::Money),
+    ("ValorCofins", "Servico/Valores/ValorCofins", Kind::Money),
+    ("ValorInss", "Servico/Valores/ValorInss", Kind::Money),
+    ("ValorIr", "Servico/Valores/ValorIr", Kind::Money),
+    ("ValorCsll", "Servico/Valores/ValorCsll", Kind::Money),
+    ("IssRetido", "Servico/Valores/IssRetido", Kind::Text),
+    ("ValorIss", "Servico/Valores/ValorIss", Kind::Money),
+    ("OutrasRetencoes", "Servico/Valores/OutrasRetencoes", Kind::Money),
+    ("BaseCalculo", "Servico/Valores/BaseCalculo", Kind::Money),
+    ("Aliquota", "Servico/Valores/Aliquota", Kind::Rate),
+    ("ValorLiquidoNfse", "Servico/Valores/ValorLiquidoNfse", Kind::Money),
+    ("DescontoIncondicionado", "Servico/Valores/DescontoIncondicionado", Kind::Money),
+    ("DescontoCondicionado", "Servico/Valores/DescontoCondicionado", Kind::Money),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct CsvSettings {
+    pub(crate) delimiter: char,
+    // Vírgula decimal, como o Excel em português espera
+    pub(crate) decimal_comma: bool,
+}
+
+impl Default for CsvSettings {
+    fn default() -> Self {
+        CsvSettings { delimiter: ';', decimal_comma: true }
+    }
+}
+
+impl CsvSettings {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if matches!(self.delimiter, '"' | '\n' | '\r') || self.delimiter.is_alphanumeric() {
+            return Err(format!("Delimitador de CSV inválido: {:?}", self.delimiter));
+        }
+        if self.decimal_comma && self.delimiter == ',' {
+            return Err("Com vírgula decimal, o delimitador do CSV não pode ser vírgula".to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn field(&self, text: &str) -> String {
+        if text.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// Situação da nota mantida na saída pela política "flag"
+fn situacao(nota: &Value) -> &'static str {
+    if nota.get("NfseSubstituicao").is_some() {
+        "substituída"
+    } else if nota.get("NfseCancelamento").is_some() {
+        "cancelada"
+    } else {
+        "normal"
+    }
+}
+
+fn decimal(nota: &Value, path: &str, kind: Kind) -> Option<Decimal> {
+    let texto = text_at(nota, path)?;
+    let mut valor = Decimal::from_str(texto.trim()).or_else(|_| Decimal::from_scientific(texto.trim())).ok()?;
+    if kind == Kind::Money {
+        valor.rescale(2);
+    } else {
+        valor = valor.normalize();
+    }
+    Some(valor)
+}
+
+fn header() -> impl Iterator<Item = &'static str> {
+    COLUNAS.iter().map(|(titulo, _, _)| *titulo).chain(["Situacao"])
+}
+
+pub(crate) fn render_csv(notas: &[Value], settings: &CsvSettings) -> String {
+    let separador = settings.delimiter.to_string();
+    let mut linhas = vec![header().map(|titulo| settings.field(titulo)).collect::<Vec<_>>().join(&separador)];
+    for nota in notas {
+        let mut campos: Vec<String> = COLUNAS
+            .iter()
+            .map(|(_, path, kind)| match kind {
+                Kind::Text => text_at(nota, path).unwrap_or_default(),
+                // Valor que não é número sai como veio, para não sumir da planilha
+                _ => match decimal(nota, path, *kind) {
+                    Some(valor) if settings.decimal_comma => valor.to_string().replace('.', ","),
+                    Some(valor) => valor.to_string(),
+                    None => text_at(nota, path).unwrap_or_default(),
+                },
+            })
+            .collect();
+        campos.push(situacao(nota).to_string());
+        linhas.push(campos.iter().map(|campo| settings.field(campo)).collect::<Vec<_>>().join(&separador));
+    }
+    linhas.join("\r\n") + "\r\n"
+}
+
+// Valores como números de verdade na planilha; identificadores (CNPJ, Numero) como texto para
+// não perder os zeros à esquerda
+pub(crate) fn render_xlsx(notas: &[Value]) -> Result<Vec<u8>, String> {
+    let erro = |e: rust_xlsxwriter::XlsxError| format!("Erro ao gerar a planilha: {}", e);
+    let mut workbook = Workbook::new();
+    let negrito = Format::new().set_bold();
+    let dinheiro = Format::new().set_num_format("#,##0.00");
+
+    let planilha = workbook.add_worksheet();
+    planilha.set_name("Notas").map_err(erro)?;
+    for (coluna, titulo) in header().enumerate() {
+        planilha.write_string_with_format(0, coluna as u16, titulo, &negrito).map_err(erro)?;
+    }
+
+    for (indice, nota) in notas.iter().enumerate() {
+        let linha = indice as u32 + 1;
+        for (coluna, (_, path, kind)) in COLUNAS.iter().enumerate() {
+            let coluna = coluna as u16;
+            match (kind, decimal(nota, path, *kind).and_then(|valor| f64::try_from(valor).ok())) {
+                (Kind::Money, Some(valor)) => planilha.write_number_with_format(linha, coluna, valor, &dinheiro).map_err(erro)?,
+                (Kind::Rate, Some(valor)) => planilha.write_number(linha, coluna, valor).map_err(erro)?,
+                _ => planilha.write_string(linha, coluna, text_at(nota, path).unwrap_or_default()).map_err(erro)?,
+            };
+        }
+        planilha.write_string(linha, COLUNAS.len() as u16, situacao(nota)).map_err(erro)?;
+    }
+
+    planilha.set_freeze_panes(1, 0).map_err(erro)?;
+    planilha.autofit();
+    workbook.save_to_buffer().map_err(erro)
+}
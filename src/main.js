@@ -22,6 +22,8 @@ function getTauriInvoke() {
 // Simple direct approach for Tauri v2
 let selectedFilePath = null;
 let selectedFileContent = null;
+// Com mais de um arquivo, as notas são juntadas num único lote
+let selectedFiles = [];
 
 document.addEventListener('DOMContentLoaded', () => {
   console.log('DOM Content Loaded - Initializing application...');  // Check if Tauri is available
@@ -58,6 +60,10 @@ document.addEventListener('DOMContentLoaded', () => {
   const fileInput = document.getElementById('fileInput');
   const uploadBtn = document.getElementById('uploadBtn');
   const convertBtn = document.getElementById('convertBtn');
+  const analyzeBtn = document.getElementById('analyzeBtn');
+  const treeJsonBtn = document.getElementById('treeJsonBtn');
+  const jsonToXmlBtn = document.getElementById('jsonToXmlBtn');
+  const flattenBtn = document.getElementById('flattenBtn');
 
   // Check if all elements exist
   if (!fileInput) {
@@ -75,6 +81,69 @@ document.addEventListener('DOMContentLoaded', () => {
 
   console.log('All elements found, setting up event listeners...');
 
+  // Perfis de empresa (valores padrão para campos ausentes), gravados no diretório de configuração
+  const companyProfile = document.getElementById('companyProfile');
+
+  function showCompanyProfiles(store) {
+    companyProfile.innerHTML = '';
+    for (const profile of store.profiles) {
+      const option = document.createElement('option');
+      option.value = profile.name;
+      option.textContent = profile.name;
+      option.selected = profile.name === store.selected;
+      companyProfile.appendChild(option);
+    }
+  }
+
+  const invokeProfiles = getTauriInvoke();
+  if (invokeProfiles) {
+    invokeProfiles('list_company_profiles')
+      .then(showCompanyProfiles)
+      .catch((error) => console.error('Erro ao carregar perfis de empresa:', error));
+
+    companyProfile.addEventListener('change', async () => {
+      try {
+        showCompanyProfiles(await invokeProfiles('select_company_profile', { name: companyProfile.value }));
+      } catch (error) {
+        document.getElementById('messageText').textContent = `Erro: ${error}`;
+        document.getElementById('message').style.display = 'block';
+      }
+    });
+  }
+
+  // Convenções da saída em JSON (notas e árvore do XML)
+  function readJsonSettings() {
+    return {
+      pretty: document.getElementById('jsonPretty').checked,
+      attributePrefix: document.getElementById('jsonAttributePrefix').value,
+      textKey: document.getElementById('jsonTextKey').value,
+      arrays: document.getElementById('jsonArrays').value.split(',').map((nome) => nome.trim()).filter((nome) => nome)
+    };
+  }
+
+  // Filtro das notas: campos vazios não filtram
+  function readFilter() {
+    const text = (id) => document.getElementById(id).value.trim() || null;
+    const list = (id) => document.getElementById(id).value.split(',').map((item) => item.trim()).filter((item) => item);
+    const number = (id) => {
+      const value = document.getElementById(id).value;
+      return value === '' ? null : Number(value);
+    };
+    const issRetido = document.getElementById('filterIssRetido').value;
+    return {
+      emissaoFrom: text('filterEmissaoFrom'),
+      emissaoTo: text('filterEmissaoTo'),
+      competenciaFrom: text('filterCompetenciaFrom'),
+      competenciaTo: text('filterCompetenciaTo'),
+      prestadores: list('filterPrestadores'),
+      tomadores: list('filterTomadores'),
+      issRetido: issRetido === '' ? null : issRetido === 'true',
+      itens: list('filterItens'),
+      valorMin: number('filterValorMin'),
+      valorMax: number('filterValorMax')
+    };
+  }
+
   // Drag and drop functionality
   const fileInfo = document.getElementById('fileInfo');
 
@@ -92,28 +161,43 @@ document.addEventListener('DOMContentLoaded', () => {
     e.preventDefault();
     fileInfo.classList.remove('drag-over');
 
-    const files = e.dataTransfer.files;
+    const files = Array.from(e.dataTransfer.files);
     if (files.length > 0) {
-      const file = files[0];
-      if (file.name.toLowerCase().endsWith('.xml')) {
-        handleFileSelection(file);
+      // XML ou pacote JSON baixado do ADN (Sistema Nacional NFS-e)
+      const aceitos = files.every((file) => {
+        const fileName = file.name.toLowerCase();
+        return fileName.endsWith('.xml') || fileName.endsWith('.json');
+      });
+      if (aceitos) {
+        handleFileSelection(files);
       } else {
-        document.getElementById('messageText').textContent = 'Por favor, selecione apenas arquivos XML.';
+        document.getElementById('messageText').textContent = 'Por favor, selecione apenas arquivos XML ou JSON do ADN.';
         document.getElementById('message').style.display = 'block';
       }
     }
   });
 
   // Function to handle file selection (both from input and drag-drop)
-  async function handleFileSelection(file) {
-    console.log('File selected:', file.name);
+  async function handleFileSelection(files) {
+    console.log('Files selected:', files.map((file) => file.name));
     try {
+      const file = files[0];
       selectedFilePath = file.name;
       selectedFileContent = await file.text();
+      selectedFiles = await Promise.all(files.map(async (item) => ({ name: item.name, content: await item.text() })));
 
-      document.getElementById('fileName').textContent = file.name;
-      document.getElementById('fileStatus').textContent = 'Arquivo pronto para conversão';
+      if (files.length > 1) {
+        document.getElementById('fileName').textContent = `${files.length} arquivos`;
+        document.getElementById('fileStatus').textContent = 'Arquivos prontos para juntar num único lote';
+      } else {
+        document.getElementById('fileName').textContent = file.name;
+        document.getElementById('fileStatus').textContent = 'Arquivo pronto para conversão';
+      }
       document.getElementById('convertBtn').disabled = false;
+      document.getElementById('analyzeBtn').disabled = false;
+      document.getElementById('treeJsonBtn').disabled = false;
+      document.getElementById('jsonToXmlBtn').disabled = !file.name.toLowerCase().endsWith('.json');
+      document.getElementById('flattenBtn').disabled = false;
 
       // Add visual feedback that file is loaded
       document.getElementById('fileInfo').classList.add('has-file');
@@ -134,16 +218,21 @@ document.addEventListener('DOMContentLoaded', () => {
 
   fileInput.addEventListener('change', async (event) => {
     console.log('File input changed');
-    const file = event.target.files[0];
-    if (file) {
-      await handleFileSelection(file);
+    const files = Array.from(event.target.files);
+    if (files.length > 0) {
+      await handleFileSelection(files);
     } else {
       console.log('No file selected');
       document.getElementById('fileName').textContent = 'Nenhum arquivo selecionado';
       document.getElementById('fileStatus').textContent = 'Clique em "Anexar XML" ou arraste um arquivo XML aqui';
       document.getElementById('convertBtn').disabled = true;
+      document.getElementById('analyzeBtn').disabled = true;
+      document.getElementById('treeJsonBtn').disabled = true;
+      document.getElementById('jsonToXmlBtn').disabled = true;
+      document.getElementById('flattenBtn').disabled = true;
       selectedFilePath = null;
       selectedFileContent = null;
+      selectedFiles = [];
       document.getElementById('fileInfo').classList.remove('has-file');
     }
   });
@@ -164,25 +253,85 @@ document.addEventListener('DOMContentLoaded', () => {
       document.getElementById('message').style.display = 'none';
 
       // Chamar comando Rust para converter o XML e abrir diálogo de salvamento
-      console.log('Invoking convert_and_save_xml_with_dialog...');
+      console.log('Invoking conversion command...');
 
       const tauriInvoke = getTauriInvoke();
       if (!tauriInvoke) {
         throw new Error('Função invoke do Tauri não encontrada');
       }
 
-      const savedPath = await tauriInvoke('convert_and_save_xml_with_dialog', {
-        xmlContent: selectedFileContent,
-        originalFileName: selectedFilePath
-      });
-      console.log('Conversion and save successful:', savedPath);
+      const reportFormat = document.getElementById('reportFormat').value;
+      const options = {
+        outputFormat: document.getElementById('outputFormat').value,
+        reportFormat: reportFormat || null,
+        summaryFormat: document.getElementById('summaryFormat').value || null,
+        partial: document.getElementById('partialMode').checked,
+        quarantine: document.getElementById('quarantine').checked,
+        cancelled: document.getElementById('cancelledPolicy').value,
+        filter: readFilter(),
+        split: document.getElementById('splitBy').value || null,
+        splitTemplate: document.getElementById('splitTemplate').value.trim() || null,
+        csv: {
+          delimiter: document.getElementById('csvDelimiter').value,
+          decimalComma: document.getElementById('csvDecimalComma').checked
+        },
+        json: readJsonSettings()
+      };
+      const result = selectedFiles.length > 1
+        ? await tauriInvoke('merge_and_save_xml_with_dialog', { files: selectedFiles, options })
+        : await tauriInvoke('convert_and_save_xml_with_dialog', {
+          xmlContent: selectedFileContent,
+          originalFileName: selectedFilePath,
+          options
+        });
+      console.log('Conversion and save successful:', result);
 
       // Ocultar indicador de carregamento
       document.getElementById('loading').style.display = 'none';
       document.getElementById('message').style.display = 'block';
 
-      // Mostrar mensagem de sucesso com o caminho onde foi salvo
-      document.getElementById('messageText').textContent = `Arquivo salvo com sucesso em: ${savedPath}`;
+      // Mostrar mensagem de sucesso com o caminho onde foi salvo e o resumo do relatório
+      const notas = result.report.notas;
+      const comValoresPadrao = notas.filter((nota) => nota.defaulted.length > 0).length;
+      const inconsistentes = notas.filter((nota) => nota.inconsistencies.length > 0).length;
+      let message = `Arquivo salvo com sucesso em: ${result.savedPath}`;
+      if (notas.length > 0) {
+        message += ` (${notas.length} notas, ${comValoresPadrao} com valores padrão`;
+        message += inconsistentes > 0 ? `, ${inconsistentes} com valores inconsistentes)` : ')';
+      }
+      const canceladas = notas.filter((nota) => nota.status.cancelled || nota.status.substituted_by).length;
+      if (canceladas > 0) {
+        message += `. ${canceladas} nota(s) cancelada(s) ou substituída(s)`;
+      }
+      if (result.report.duplicates.length > 0) {
+        const conflitos = result.report.duplicates.filter((duplicada) => duplicada.conflicts.length > 0).length;
+        message += `. ${result.report.duplicates.length} nota(s) repetida(s), ${conflitos} com versões diferentes`;
+      }
+      if (result.report.filtered_out > 0) {
+        message += `. ${result.report.filtered_out} nota(s) fora do filtro`;
+      }
+      if (result.report.failures.length > 0) {
+        message += `. ${result.report.failures.length} nota(s) rejeitada(s)`;
+      }
+      if (result.report.ignored_events.length > 0) {
+        message += `. ${result.report.ignored_events.length} evento(s) do ADN ignorado(s)`;
+      }
+      if (result.summary.total.notas > 0) {
+        message += `. Total de serviços: R$ ${result.summary.total.valor_servicos}, ISS: R$ ${result.summary.total.valor_iss}`;
+      }
+      if (result.reportPath) {
+        message += `. Relatório: ${result.reportPath}`;
+      }
+      if (result.quarantinePath) {
+        message += `. Quarentena: ${result.quarantinePath}`;
+      }
+      if (result.cancelledPath) {
+        message += `. Canceladas: ${result.cancelledPath}`;
+      }
+      if (result.summaryPath) {
+        message += `. Resumo: ${result.summaryPath}`;
+      }
+      document.getElementById('messageText').textContent = message;
 
     } catch (error) {
       console.error('Erro na conversão:', error);
@@ -198,5 +347,172 @@ document.addEventListener('DOMContentLoaded', () => {
     }
   });
 
+  // Salvar a árvore intermediária do XML (antes da conversão das notas) em JSON
+  treeJsonBtn.addEventListener('click', async (e) => {
+    e.preventDefault();
+    try {
+      document.getElementById('loading').style.display = 'block';
+      document.getElementById('message').style.display = 'none';
+
+      const tauriInvoke = getTauriInvoke();
+      if (!tauriInvoke) {
+        throw new Error('Função invoke do Tauri não encontrada');
+      }
+      const savedPath = await tauriInvoke('save_xml_tree_json_with_dialog', {
+        xmlContent: selectedFileContent,
+        originalFileName: selectedFilePath,
+        settings: readJsonSettings()
+      });
+
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      document.getElementById('messageText').textContent = `Árvore JSON salva em: ${savedPath}`;
+
+    } catch (error) {
+      console.error('Erro ao exportar a árvore JSON:', error);
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      if (error.toString().includes('cancelada')) {
+        document.getElementById('messageText').textContent = 'Operação cancelada.';
+      } else {
+        document.getElementById('messageText').textContent = `Erro: ${error}`;
+      }
+    }
+  });
+
+  // Montar XML a partir do JSON selecionado (correções ou saída de outra ferramenta)
+  jsonToXmlBtn.addEventListener('click', async (e) => {
+    e.preventDefault();
+    try {
+      document.getElementById('loading').style.display = 'block';
+      document.getElementById('message').style.display = 'none';
+
+      const tauriInvoke = getTauriInvoke();
+      if (!tauriInvoke) {
+        throw new Error('Função invoke do Tauri não encontrada');
+      }
+      const options = {
+        json: readJsonSettings(),
+        xml: {
+          indent: Number(document.getElementById('xmlIndent').value) || 0,
+          declaration: document.getElementById('xmlDeclaration').checked
+        }
+      };
+      const savedPath = await tauriInvoke('convert_json_to_xml_with_dialog', {
+        jsonContent: selectedFileContent,
+        originalFileName: selectedFilePath,
+        options
+      });
+
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      document.getElementById('messageText').textContent = `XML salvo em: ${savedPath}`;
+
+    } catch (error) {
+      console.error('Erro na conversão de JSON para XML:', error);
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      if (error.toString().includes('cancelada')) {
+        document.getElementById('messageText').textContent = 'Operação cancelada.';
+      } else {
+        document.getElementById('messageText').textContent = `Erro: ${error}`;
+      }
+    }
+  });
+
+  // XML fora de NFS-e (catálogos, extratos): uma linha de CSV por registro
+  flattenBtn.addEventListener('click', async (e) => {
+    e.preventDefault();
+    try {
+      document.getElementById('loading').style.display = 'block';
+      document.getElementById('message').style.display = 'none';
+
+      const tauriInvoke = getTauriInvoke();
+      if (!tauriInvoke) {
+        throw new Error('Função invoke do Tauri não encontrada');
+      }
+      const options = {
+        recordPath: document.getElementById('flattenRecordPath').value.trim(),
+        columns: document.getElementById('flattenColumns').value.split(',').map((coluna) => coluna.trim()).filter((coluna) => coluna),
+        joinSeparator: document.getElementById('flattenJoinSeparator').value,
+        csv: { delimiter: document.getElementById('csvDelimiter').value }
+      };
+      const result = await tauriInvoke('flatten_xml_to_csv_with_dialog', {
+        xmlContent: selectedFileContent,
+        originalFileName: selectedFilePath,
+        options
+      });
+
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      document.getElementById('messageText').textContent =
+        `CSV salvo em: ${result.savedPath} (${result.records} registros, ${result.columns.length} colunas)`;
+
+    } catch (error) {
+      console.error('Erro no achatamento do XML:', error);
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      if (error.toString().includes('cancelada')) {
+        document.getElementById('messageText').textContent = 'Operação cancelada.';
+      } else {
+        document.getElementById('messageText').textContent = `Erro: ${error}`;
+      }
+    }
+  });
+
+  // Conferir a numeração de cada prestador nas notas dos arquivos selecionados
+  analyzeBtn.addEventListener('click', async (e) => {
+    e.preventDefault();
+    try {
+      document.getElementById('loading').style.display = 'block';
+      document.getElementById('message').style.display = 'none';
+
+      const tauriInvoke = getTauriInvoke();
+      if (!tauriInvoke) {
+        throw new Error('Função invoke do Tauri não encontrada');
+      }
+      const options = { partial: document.getElementById('partialMode').checked };
+      const result = await tauriInvoke('analyze_numbering', { files: selectedFiles, options });
+
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+
+      // Mesmo formato das mensagens de conversão: um resumo e os detalhes separados por ponto
+      let message = `Numeração conferida: ${result.prestadores.length} prestador(es)`;
+      for (const sequencia of result.prestadores) {
+        const nome = sequencia.razao_social ? `${sequencia.prestador} (${sequencia.razao_social})` : sequencia.prestador;
+        const problemas = [];
+        if (sequencia.missing.length > 0) {
+          const faixas = sequencia.missing.map((faixa) => faixa.from === faixa.to ? faixa.from : `${faixa.from}–${faixa.to}`);
+          problemas.push(`faltando ${faixas.join(', ')}`);
+        }
+        if (sequencia.duplicates.length > 0) {
+          problemas.push(`repetidas ${sequencia.duplicates.map((item) => item.numero).join(', ')}`);
+        }
+        if (sequencia.reused.length > 0) {
+          problemas.push(`número reaproveitado com outro código de verificação ${sequencia.reused.map((item) => item.numero).join(', ')}`);
+        }
+        if (sequencia.out_of_order.length > 0) {
+          problemas.push(`emitidas fora de ordem ${sequencia.out_of_order.map((item) => item.numero).join(', ')}`);
+        }
+        if (sequencia.invalid.length > 0) {
+          problemas.push(`número inválido ${sequencia.invalid.join(', ')}`);
+        }
+        const resultado = problemas.length > 0 ? problemas.join('; ') : 'sequência sem lacunas';
+        message += `. ${nome}: ${sequencia.first} a ${sequencia.last} (${sequencia.count} números), ${resultado}`;
+      }
+      if (result.failures.length > 0) {
+        message += `. ${result.failures.length} nota(s) rejeitada(s)`;
+      }
+      document.getElementById('messageText').textContent = message;
+
+    } catch (error) {
+      console.error('Erro na análise da numeração:', error);
+      document.getElementById('loading').style.display = 'none';
+      document.getElementById('message').style.display = 'block';
+      document.getElementById('messageText').textContent = `Erro: ${error}`;
+    }
+  });
+
   console.log('Application initialized successfully');
 });